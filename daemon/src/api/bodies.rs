@@ -26,6 +26,10 @@ pub struct ApiResultQuery {
     pub id: u64,
     /// CID of the document to fetch
     pub cid: String,
+    /// Path of the document as returned by the provider, with segments separated by `/`.
+    /// The file name tells documents apart when their content is ambiguous, such as Markdown and plain text.
+    #[serde(default)]
+    pub path: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -33,3 +37,9 @@ pub struct ApiVersionResponse {
     /// Version of the protocol
     pub version: u64,
 }
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiCarUploadResponse {
+    /// Root cids of the uploaded CAR file, that will be indexed
+    pub roots: Vec<String>,
+}
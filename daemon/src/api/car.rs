@@ -0,0 +1,83 @@
+use super::*;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use warp::hyper::body::Buf;
+
+fn error_response(status: u16, message: impl Into<String>) -> Response<String> {
+    Response::builder().status(status).body(message.into()).unwrap()
+}
+
+/// Writes the uploaded body to a temporary file in the car directory, and returns its path and sha256 hash
+async fn save_upload<S, B>(mut body: S, car_dir: &str) -> Result<(String, String), String>
+where
+    S: Stream<Item = Result<B, warp::Error>> + Unpin,
+    B: Buf,
+{
+    tokio::fs::create_dir_all(car_dir).await.map_err(|e| format!("Failed to create car directory: {e}"))?;
+    let tmp_path = format!("{car_dir}/upload-{}.tmp", rand::random::<u64>());
+    let mut file = tokio::fs::File::create(&tmp_path).await.map_err(|e| format!("Failed to create file: {e}"))?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = body.next().await {
+        let mut chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                return Err(format!("Failed to receive upload: {e}"));
+            }
+        };
+        while chunk.has_remaining() {
+            let bytes = chunk.chunk();
+            hasher.update(bytes);
+            if let Err(e) = file.write_all(bytes).await {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                return Err(format!("Failed to write upload: {e}"));
+            }
+            let len = bytes.len();
+            chunk.advance(len);
+        }
+    }
+    file.flush().await.map_err(|e| format!("Failed to write upload: {e}"))?;
+    let hash = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect::<String>();
+    Ok((tmp_path, hash))
+}
+
+pub(super) async fn upload_car<S, B>((body, cars, config): (S, Arc<CarSource>, Arc<Args>)) -> Result<impl warp::Reply, Infallible>
+where
+    S: Stream<Item = Result<B, warp::Error>> + Unpin,
+    B: Buf,
+{
+    if !config.car_uploads {
+        return Ok(error_response(403, "CAR uploads are disabled"));
+    }
+
+    let (tmp_path, hash) = match save_upload(body, &config.car_dir).await {
+        Ok(saved) => saved,
+        Err(e) => {
+            error!("{e}");
+            return Ok(error_response(500, e));
+        }
+    };
+    let path = format!("{}/{hash}.car", config.car_dir);
+    if let Err(e) = tokio::fs::rename(&tmp_path, &path).await {
+        error!("Failed to move uploaded CAR file: {e}");
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Ok(error_response(500, "Failed to store CAR file"));
+    }
+
+    let path2 = path.clone();
+    let roots = match tokio::task::spawn_blocking(move || cars.add_archive(&path2)).await {
+        Ok(Ok(roots)) => roots,
+        Ok(Err(e)) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Ok(error_response(400, format!("Invalid CAR file: {e}")));
+        },
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            error!("Failed to index uploaded CAR file: {e}");
+            return Ok(error_response(500, "Failed to index CAR file"));
+        }
+    };
+    info!("Uploaded CAR file {path} with roots {roots:?}");
+
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&ApiCarUploadResponse { roots }).unwrap()).unwrap())
+}
@@ -8,12 +8,14 @@ mod local_search;
 mod search;
 mod results;
 mod version;
+mod car;
 use {
     local_search::*,
     bodies::*,
     search::*,
     results::*,
     version::*,
+    car::*,
 };
 
 struct OngoingSearch {
@@ -73,13 +75,14 @@ impl SearchPark {
     }
 }
 
-pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc<SearchPark>, kamilata: NodeController) {
+pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, cars: Arc<CarSource>, search_park: Arc<SearchPark>, kamilata: NodeController) {
     let hello_world = warp::path::end().map(|| "Hello, World at root!");
 
+    let index2 = index.clone();
     let local_search = warp::get()
         .and(warp::path("local-search"))
         .and(warp::query::<ApiSearchQuery>())
-        .map(move |q: ApiSearchQuery| (q, index.clone()))
+        .map(move |q: ApiSearchQuery| (q, index2.clone()))
         .and_then(local_search);
     
     let search_park2 = Arc::clone(&search_park);
@@ -105,13 +108,20 @@ pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc
         .map(move |id: ApiResultsQuery| (id, Arc::clone(&search_park2)))
         .and_then(fetch_results);
 
-    let config2 = Arc::clone(&config);
     let result = warp::get()
         .and(warp::path("result"))
         .and(warp::query::<ApiResultQuery>())
-        .map(move |q: ApiResultQuery| (q, Arc::clone(&search_park), Arc::clone(&config2)))
+        .map(move |q: ApiResultQuery| (q, Arc::clone(&search_park), index.clone()))
         .and_then(get_result);
 
+    let config2 = Arc::clone(&config);
+    let car_upload = warp::post()
+        .and(warp::path("car"))
+        .and(warp::body::content_length_limit(config.car_upload_limit))
+        .and(warp::body::stream())
+        .map(move |body| (body, Arc::clone(&cars), Arc::clone(&config2)))
+        .and_then(upload_car);
+
     let version = warp::get()
         .and(warp::path("version"))
         .and_then(version);
@@ -131,6 +141,7 @@ pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc
             .or(fetch_results)
             .or(version)
             .or(result)
+            .or(car_upload)
     ).with(cors);
 
     warp::serve(routes).run(config.api_addr.parse::<SocketAddr>().expect("Invalid api_addr")).await;
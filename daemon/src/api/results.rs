@@ -10,13 +10,14 @@ pub(super) async fn fetch_results((query, search_park): (ApiResultsQuery, Arc<Se
     Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&search_results).unwrap()).unwrap())
 }
 
-pub(super) async fn get_result((q, search_park, config): (ApiResultQuery, Arc<SearchPark>, Arc<Args>)) -> Result<impl warp::Reply, Infallible> {
+pub(super) async fn get_result((q, search_park, index): (ApiResultQuery, Arc<SearchPark>, DocumentIndex)) -> Result<impl warp::Reply, Infallible> {
     let id = q.id as usize;
     let cid = q.cid;
     let query = match search_park.get_query(id).await {
         Some(query) => query,
         None => return Ok(Response::builder().status(400).body("Search not found".to_string()).unwrap()),
     };
-    let result = cid_to_result(query, cid, Vec::new(), config).await;
+    let paths = q.path.map(|path| vec![path.split('/').map(String::from).collect()]).unwrap_or_default();
+    let result = cid_to_result(query, cid, paths, index.source()).await;
     Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&result).unwrap()).unwrap())
 }
@@ -1,6 +1,16 @@
 use crate::prelude::*;
 use clap::ArgAction::Set;
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Kubo RPC
+    Kubo,
+    /// Read-only HTTP gateway
+    Gateway,
+    /// Only CAR files
+    Car,
+}
+
 /// Admarus search engine daemon
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -18,10 +28,59 @@ pub struct Args {
     #[arg(long, default_value = "http://localhost:5001")]
     pub ipfs_rpc: String,
 
+    /// Where documents are read from
+    /// DNS pins and getting peers from IPFS require Kubo.
+    #[arg(long, value_enum, default_value = "kubo")]
+    pub source: SourceKind,
+
+    /// HTTP gateway url, used by the gateway source
+    /// The gateway must support trustless requests for raw blocks.
+    #[arg(long, default_value = "http://localhost:8080")]
+    pub gateway_url: String,
+
+    /// Root cids to index with the gateway source
+    #[arg(long)]
+    pub source_roots: Vec<String>,
+
+    /// CAR files to index, in addition to the content of the source
+    #[arg(long)]
+    pub car_files: Vec<String>,
+
+    /// Directory where uploaded CAR files are stored
+    /// CAR files in this directory are indexed at startup.
+    #[arg(long, default_value = "admarus-cars")]
+    pub car_dir: String,
+
+    /// Enables uploading CAR files to index through the API
+    #[arg(long, default_value = "false", action = Set)]
+    pub car_uploads: bool,
+
+    /// Maximum size of uploaded CAR files (in bytes)
+    #[arg(long, default_value = "1000000000")]
+    pub car_upload_limit: u64,
+
     /// Enables getting peers from IPFS
     #[arg(long, default_value = "false", action = Set)]
     pub ipfs_peers_enabled: bool,
 
+    /// Path to the file storing the keypair of the node
+    /// It is created on first run so that the peer id stays the same across restarts.
+    #[arg(long, default_value = "admarus.key")]
+    pub keypair_path: String,
+
+    /// Path to a protobuf-encoded keypair to import (one-shot)
+    /// It replaces the keypair stored at keypair_path, which is moved to a timestamped keypair_path.<time>.old backup.
+    /// Importing the same keypair again does nothing.
+    #[arg(long)]
+    pub import_keypair: Option<String>,
+
+    /// Generates a new keypair, replacing the one stored at keypair_path (one-shot)
+    /// The previous keypair is moved to a timestamped keypair_path.<time>.old backup.
+    /// A keypair_path.rotated file records the rotation, so restarts with this still enabled keep the new keypair.
+    /// Disabling this removes that file, so that enabling it again rotates the keypair again.
+    #[arg(long, default_value = "false", action = Set)]
+    pub rotate_keypair: bool,
+
     /// Census public RPC url
     #[arg(long, default_value = "https://census.admarus.net")]
     pub census_rpc: String,
@@ -55,10 +114,45 @@ pub struct Args {
     pub leechers: usize,
 
     /// Whether to also crawl unprioritized documents
-    /// Prioritized documents are documents named with a supported extension
+    /// Prioritized documents are documents named with a supported extension or without extension.
+    /// Other files are only indexed if their content turns out to be supported.
     #[arg(long, default_value = "false", action = Set)]
     pub crawl_unprioritized: bool,
 
+    /// Maximum number of documents fetched and parsed concurrently while indexing
+    #[arg(long, default_value = "16")]
+    pub load_parallelism: usize,
+
+    /// Whether to also index the stems of words, so that queries match other forms of the same words.
+    /// Stems are only computed for documents in a supported language, and make the index larger.
+    /// Queries are matched against stems when their language is set with `lang=` or can be detected.
+    #[arg(long, default_value = "false", action = Set)]
+    pub stemming: bool,
+
+    /// Whether to date documents that don't tell when they were published by their UnixFS modification time.
+    /// This takes an additional request to the IPFS node for each such document.
+    #[arg(long, default_value = "false", action = Set)]
+    pub mtime_dates: bool,
+
+    /// Cids that are never indexed, along with everything they contain
+    #[arg(long)]
+    pub exclude_cids: Vec<String>,
+
+    /// Glob patterns of paths that are not indexed, relative to pinned roots
+    /// Examples: /backups/** or **/*.key
+    #[arg(long)]
+    pub exclude_paths: Vec<String>,
+
+    /// Glob patterns of Kubo pin names whose content is not indexed
+    #[arg(long)]
+    pub exclude_pin_names: Vec<String>,
+
+    /// Maximum size of indexed documents (in bytes)
+    /// Documents are read up to 15MB anyway. Larger documents are skipped before being fetched when their directory listing gives their size.
+    /// Documents indexed before the limit was set are removed the next time their directory is listed, including from stored listings.
+    #[arg(long)]
+    pub max_document_size: Option<usize>,
+
     /// Path to the database.
     /// Admarus does not require using a database, which is fine under 10000 documents.
     #[cfg_attr(any(feature = "database-lmdb", feature = "database-mdbx"), arg(long, default_value = "admarus.mdb"))]
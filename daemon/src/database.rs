@@ -6,6 +6,20 @@ use bimap::BiHashMap;
 
 type LEU32 = U32<LE>;
 
+struct Tables {
+    index: HeedDatabase<Str, ByteSlice>,
+    cids: HeedDatabase<OwnedType<LEU32>, Str>,
+    ancestors: HeedDatabase<OwnedType<LEU32>, ByteSlice>,
+    folders: HeedDatabase<OwnedType<LEU32>, Unit>,
+    filters: HeedDatabase<Str, ByteSlice>,
+    listed: HeedDatabase<Str, OwnedType<LEU32>>,
+    listed_entries: HeedDatabase<Str, SerdeJson<ListedEntry>>,
+    document_keys: HeedDatabase<OwnedType<LEU32>, SerdeJson<DocumentKeys>>,
+}
+
+/// Index words and filter keys (`name=value`) of a document, so that it can be removed without scanning the whole index
+pub type DocumentKeys = (Vec<String>, Vec<String>);
+
 #[derive(Debug)]
 pub enum DbError {
     CommandChannelUnavailable,
@@ -37,12 +51,60 @@ impl DbController {
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 
+    async fn filters_get(&self, keys: Vec<(String, String)>) -> Result<Vec<((String, String), Vec<LocalCid>)>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::FiltersGet{keys, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn filters_put(&self, items: Vec<((String, String), Vec<LocalCid>)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::FiltersPut{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
     async fn put_cids(&self, items: Vec<(LocalCid, String)>) -> Result<(), DbError> {
         let (sender, receiver) = oneshot_channel();
         self.sender.send(DbCommand::PutCids{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 
+    async fn put_ancestors(&self, items: Vec<(LocalCid, HashMap<LocalCid, String>)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PutAncestors{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn put_folders(&self, items: Vec<(LocalCid, bool)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PutFolders{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn get_listed(&self, cid: String, offset: u32, limit: u32) -> Result<Option<Vec<ListedEntry>>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::GetListed{cid, offset, limit, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn put_listed(&self, cid: String, offset: u32, children: Vec<ListedEntry>, complete: bool) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PutListed{cid, offset, children, complete, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn put_document_keys(&self, items: Vec<(LocalCid, DocumentKeys)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PutDocumentKeys{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn remove_cids(&self, lcids: HashSet<LocalCid>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::RemoveCids{lcids, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
     pub async fn compute_filter(&self) -> Result<Filter<FILTER_SIZE>, DbError> {
         let (sender, receiver) = oneshot_channel();
         self.sender.send(DbCommand::ComputeFilter{sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
@@ -56,7 +118,15 @@ pub struct DbIndexController(DbController);
 impl DbIndexController {
     pub async fn get(&self, keys: Vec<String>) -> Result<Vec<(String, Vec<(LocalCid, f32)>)>, DbError> { self.0.index_get(keys).await }
     pub async fn put(&self, items: Vec<(String, HashMap<LocalCid, f32>)>) -> Result<(), DbError> { self.0.index_put(items).await }
+    pub async fn get_filters(&self, keys: Vec<(String, String)>) -> Result<Vec<((String, String), Vec<LocalCid>)>, DbError> { self.0.filters_get(keys).await }
+    pub async fn put_filters(&self, items: Vec<((String, String), Vec<LocalCid>)>) -> Result<(), DbError> { self.0.filters_put(items).await }
     pub async fn put_cids(&self, items: Vec<(LocalCid, String)>) -> Result<(), DbError> { self.0.put_cids(items).await }
+    pub async fn put_ancestors(&self, items: Vec<(LocalCid, HashMap<LocalCid, String>)>) -> Result<(), DbError> { self.0.put_ancestors(items).await }
+    pub async fn put_folders(&self, items: Vec<(LocalCid, bool)>) -> Result<(), DbError> { self.0.put_folders(items).await }
+    pub async fn get_listed(&self, cid: String, offset: u32, limit: u32) -> Result<Option<Vec<ListedEntry>>, DbError> { self.0.get_listed(cid, offset, limit).await }
+    pub async fn put_listed(&self, cid: String, offset: u32, children: Vec<ListedEntry>, complete: bool) -> Result<(), DbError> { self.0.put_listed(cid, offset, children, complete).await }
+    pub async fn put_document_keys(&self, items: Vec<(LocalCid, DocumentKeys)>) -> Result<(), DbError> { self.0.put_document_keys(items).await }
+    pub async fn remove_cids(&self, lcids: HashSet<LocalCid>) -> Result<(), DbError> { self.0.remove_cids(lcids).await }
     pub async fn compute_filter(&self) -> Result<Filter<FILTER_SIZE>, DbError> { self.0.compute_filter().await }
 }
 impl From<DbController> for DbIndexController { fn from(controller: DbController) -> Self { DbIndexController(controller) } }
@@ -64,7 +134,15 @@ impl From<DbController> for DbIndexController { fn from(controller: DbController
 enum DbCommand {
     IndexGet { keys: Vec<String>, sender: OneshotSender<Result<Vec<(String, Vec<(LocalCid, f32)>)>, HeedError>> },
     IndexPut { items: Vec<(String, HashMap<LocalCid, f32>)>, sender: OneshotSender<Result<(), HeedError>> },
+    FiltersGet { keys: Vec<(String, String)>, sender: OneshotSender<Result<Vec<((String, String), Vec<LocalCid>)>, HeedError>> },
+    FiltersPut { items: Vec<((String, String), Vec<LocalCid>)>, sender: OneshotSender<Result<(), HeedError>> },
     PutCids { items: Vec<(LocalCid, String)>, sender: OneshotSender<Result<(), HeedError>> },
+    PutAncestors { items: Vec<(LocalCid, HashMap<LocalCid, String>)>, sender: OneshotSender<Result<(), HeedError>> },
+    PutFolders { items: Vec<(LocalCid, bool)>, sender: OneshotSender<Result<(), HeedError>> },
+    GetListed { cid: String, offset: u32, limit: u32, sender: OneshotSender<Result<Option<Vec<ListedEntry>>, HeedError>> },
+    PutListed { cid: String, offset: u32, children: Vec<ListedEntry>, complete: bool, sender: OneshotSender<Result<(), HeedError>> },
+    PutDocumentKeys { items: Vec<(LocalCid, DocumentKeys)>, sender: OneshotSender<Result<(), HeedError>> },
+    RemoveCids { lcids: HashSet<LocalCid>, sender: OneshotSender<Result<(), HeedError>> },
     ComputeFilter { sender: OneshotSender<Result<Filter<FILTER_SIZE>, HeedError>> },
 }
 
@@ -73,7 +151,15 @@ impl std::fmt::Debug for DbCommand {
         match self {
             DbCommand::IndexGet { keys, .. } => f.debug_struct("IndexGet").field("keys", &format!("{:?} entries", keys.len())).finish_non_exhaustive(),
             DbCommand::IndexPut { items, .. } => f.debug_struct("IndexPut").field("index", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::FiltersGet { keys, .. } => f.debug_struct("FiltersGet").field("keys", &format!("{:?} entries", keys.len())).finish_non_exhaustive(),
+            DbCommand::FiltersPut { items, .. } => f.debug_struct("FiltersPut").field("filters", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
             DbCommand::PutCids { items, .. } => f.debug_struct("PutCids").field("cids", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::PutAncestors { items, .. } => f.debug_struct("PutAncestors").field("ancestors", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::PutFolders { items, .. } => f.debug_struct("PutFolders").field("folders", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::GetListed { cid, offset, .. } => f.debug_struct("GetListed").field("cid", cid).field("offset", offset).finish_non_exhaustive(),
+            DbCommand::PutListed { cid, offset, children, .. } => f.debug_struct("PutListed").field("cid", cid).field("offset", offset).field("children", &format!("{:?} entries", children.len())).finish_non_exhaustive(),
+            DbCommand::PutDocumentKeys { items, .. } => f.debug_struct("PutDocumentKeys").field("documents", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::RemoveCids { lcids, .. } => f.debug_struct("RemoveCids").field("cids", &format!("{:?} entries", lcids.len())).finish_non_exhaustive(),
             DbCommand::ComputeFilter { .. } => f.debug_struct("ComputeFilter").finish_non_exhaustive(),
         }
     }
@@ -98,6 +184,10 @@ fn index_get(keys: Vec<String>, env: &Env, index: &HeedDatabase<Str, ByteSlice>)
 fn index_put(items: &[(String, HashMap<LocalCid, f32>)], env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
     let mut wtxn = env.write_txn()?;
     for (key, value) in items {
+        if value.is_empty() {
+            index.delete(&mut wtxn, key)?;
+            continue;
+        }
         let mut data = Vec::with_capacity(value.len() * 8);
         for (lcid, score) in value {
             data.extend_from_slice(&lcid.0.to_le_bytes());
@@ -109,6 +199,32 @@ fn index_put(items: &[(String, HashMap<LocalCid, f32>)], env: &Env, index: &Heed
     Ok(())
 }
 
+fn filters_get(keys: Vec<(String, String)>, env: &Env, filters: &HeedDatabase<Str, ByteSlice>) -> Result<Vec<((String, String), Vec<LocalCid>)>, HeedError> {
+    let rotxn = env.read_txn()?;
+    let mut items = Vec::with_capacity(keys.len());
+    for (name, value) in keys {
+        let data = filters.get(&rotxn, &format!("{name}={value}"))?.unwrap_or_default();
+        let lcids = data.chunks_exact(4).map(|chunk| LocalCid(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))).collect();
+        items.push(((name, value), lcids));
+    }
+    Ok(items)
+}
+
+fn filters_put(items: Vec<((String, String), Vec<LocalCid>)>, env: &Env, filters: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for ((name, value), lcids) in items {
+        let key = format!("{name}={value}");
+        if lcids.is_empty() {
+            filters.delete(&mut wtxn, &key)?;
+            continue;
+        }
+        let data = lcids.iter().flat_map(|lcid| lcid.0.to_le_bytes()).collect::<Vec<_>>();
+        filters.put(&mut wtxn, &key, &data)?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
 fn put_cids(items: Vec<(LocalCid, String)>, env: &Env, cids: &HeedDatabase<OwnedType<LEU32>, Str>) -> Result<(), HeedError> {
     let mut wtxn = env.write_txn()?;
     for (lcid, cid) in items {
@@ -118,19 +234,189 @@ fn put_cids(items: Vec<(LocalCid, String)>, env: &Env, cids: &HeedDatabase<Owned
     Ok(())
 }
 
-fn compute_filter(env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<Filter<FILTER_SIZE>, HeedError> {
+/// Encodes ancestors as a sequence of (ancestor lcid, name length, name)
+fn encode_ancestors(ancestors: &HashMap<LocalCid, String>) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (ancestor_lcid, name) in ancestors {
+        data.extend_from_slice(&ancestor_lcid.0.to_le_bytes());
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+    }
+    data
+}
+
+fn decode_ancestors(mut data: &[u8]) -> HashMap<LocalCid, String> {
+    let mut ancestors = HashMap::new();
+    while data.len() >= 8 {
+        let ancestor_lcid = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let name_len = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let Some(name) = data.get(8..8+name_len) else {
+            warn!("Truncated ancestor entry in database");
+            break;
+        };
+        ancestors.insert(LocalCid(ancestor_lcid), String::from_utf8_lossy(name).into_owned());
+        data = &data[8+name_len..];
+    }
+    ancestors
+}
+
+fn put_ancestors(items: Vec<(LocalCid, HashMap<LocalCid, String>)>, env: &Env, ancestors: &HeedDatabase<OwnedType<LEU32>, ByteSlice>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for (lcid, lcid_ancestors) in items {
+        if lcid_ancestors.is_empty() {
+            ancestors.delete(&mut wtxn, &LEU32::new(lcid.0))?;
+        } else {
+            ancestors.put(&mut wtxn, &LEU32::new(lcid.0), &encode_ancestors(&lcid_ancestors))?;
+        }
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+fn put_folders(items: Vec<(LocalCid, bool)>, env: &Env, folders: &HeedDatabase<OwnedType<LEU32>, Unit>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for (lcid, is_folder) in items {
+        if is_folder {
+            folders.put(&mut wtxn, &LEU32::new(lcid.0), &())?;
+        } else {
+            folders.delete(&mut wtxn, &LEU32::new(lcid.0))?;
+        }
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+/// Children of listed directories are stored one per entry, so that huge directories never have to be held in memory
+fn listed_entry_key(cid: &str, i: u32) -> String {
+    format!("{cid}/{i:010}")
+}
+
+/// Returns up to `limit` children of a directory, starting at `offset`, or `None` if the directory wasn't fully listed
+fn get_listed(cid: String, offset: u32, limit: u32, env: &Env, listed: &HeedDatabase<Str, OwnedType<LEU32>>, listed_entries: &HeedDatabase<Str, SerdeJson<ListedEntry>>) -> Result<Option<Vec<ListedEntry>>, HeedError> {
+    let rotxn = env.read_txn()?;
+    let Some(count) = listed.get(&rotxn, &cid)?.map(|count| count.get()) else { return Ok(None) };
+    let mut children = Vec::new();
+    for i in offset..count.min(offset.saturating_add(limit)) {
+        if let Some(child) = listed_entries.get(&rotxn, &listed_entry_key(&cid, i))? {
+            children.push(child);
+        }
+    }
+    Ok(Some(children))
+}
+
+/// Stores children of a directory starting at `offset`.
+/// The directory is only considered listed once the last chunk is stored with `complete` set.
+fn put_listed(cid: String, offset: u32, children: Vec<ListedEntry>, complete: bool, env: &Env, listed: &HeedDatabase<Str, OwnedType<LEU32>>, listed_entries: &HeedDatabase<Str, SerdeJson<ListedEntry>>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    let count = offset + children.len() as u32;
+    for (i, child) in (offset..).zip(children) {
+        listed_entries.put(&mut wtxn, &listed_entry_key(&cid, i), &child)?;
+    }
+    if complete {
+        listed.put(&mut wtxn, &cid, &LEU32::new(count))?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+fn put_document_keys(items: Vec<(LocalCid, DocumentKeys)>, env: &Env, document_keys: &HeedDatabase<OwnedType<LEU32>, SerdeJson<DocumentKeys>>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for (lcid, keys) in items {
+        document_keys.put(&mut wtxn, &LEU32::new(lcid.0), &keys)?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+/// Removes documents from the values of a table of index words or filters.
+/// Only the given keys are updated, unless they are unknown and the whole table has to be scanned.
+fn remove_lcids_from(table: &HeedDatabase<Str, ByteSlice>, keys: Option<&HashSet<String>>, entry_size: usize, lcids: &HashSet<LocalCid>, wtxn: &mut heed::RwTxn) -> Result<(), HeedError> {
+    // Collect entries first as the table can't be modified while iterating
+    let mut entries = Vec::new();
+    match keys {
+        Some(keys) => for key in keys {
+            if let Some(data) = table.get(wtxn, key)? {
+                entries.push((key.to_owned(), data.to_vec()));
+            }
+        },
+        None => for entry in table.iter(wtxn)? {
+            let (key, data) = entry?;
+            entries.push((key.to_owned(), data.to_vec()));
+        },
+    }
+
+    for (key, data) in entries {
+        let new_data = data.chunks_exact(entry_size)
+            .filter(|chunk| !lcids.contains(&LocalCid(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        if new_data.len() == data.len() {
+            continue;
+        }
+        if new_data.is_empty() {
+            table.delete(wtxn, &key)?;
+        } else {
+            table.put(wtxn, &key, &new_data)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_cids(lcids: &HashSet<LocalCid>, env: &Env, tables: &Tables) -> Result<(), HeedError> {
+    let Tables { index, cids, ancestors, folders, filters, listed, listed_entries, document_keys } = tables;
+    let mut wtxn = env.write_txn()?;
+    let mut words = HashSet::new();
+    let mut filter_keys = HashSet::new();
+    let mut keys_known = true;
+    for lcid in lcids {
+        let key = LEU32::new(lcid.0);
+        if let Some(cid) = cids.get(&wtxn, &key)?.map(|cid| cid.to_owned()) {
+            if let Some(count) = listed.get(&wtxn, &cid)?.map(|count| count.get()) {
+                listed.delete(&mut wtxn, &cid)?;
+                for i in 0..count {
+                    listed_entries.delete(&mut wtxn, &listed_entry_key(&cid, i))?;
+                }
+            }
+        }
+        match document_keys.get(&wtxn, &key)? {
+            Some((document_words, document_filter_keys)) => {
+                words.extend(document_words);
+                filter_keys.extend(document_filter_keys);
+            },
+            // Documents indexed before their keys were stored can only be found by scanning
+            None => keys_known &= folders.get(&wtxn, &key)?.is_some(),
+        }
+        cids.delete(&mut wtxn, &key)?;
+        ancestors.delete(&mut wtxn, &key)?;
+        folders.delete(&mut wtxn, &key)?;
+        document_keys.delete(&mut wtxn, &key)?;
+    }
+
+    remove_lcids_from(index, keys_known.then_some(&words), 8, lcids, &mut wtxn)?;
+    remove_lcids_from(filters, keys_known.then_some(&filter_keys), 4, lcids, &mut wtxn)?;
+
+    wtxn.commit()?;
+    Ok(())
+}
+
+fn compute_filter(env: &Env, tables: &Tables) -> Result<Filter<FILTER_SIZE>, HeedError> {
     let mut filter = Filter::new();
 
     let rotxn = env.read_txn().expect("Failed to open read transaction to iterate over words");
-    let index = index.iter(&rotxn).expect("Failed to iterate over words").filter_map(|c| c.ok());
+    let index = tables.index.iter(&rotxn).expect("Failed to iterate over words").filter_map(|c| c.ok());
     for (word, _) in index {
         filter.add_word::<DocumentIndex>(word);
     }
+    let filters = tables.filters.iter(&rotxn).expect("Failed to iterate over filters").filter_map(|c| c.ok());
+    for (key, _) in filters {
+        filter.add_word::<DocumentIndex>(key);
+    }
 
     Ok(filter)
 }
 
-fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, cids: HeedDatabase<OwnedType<LEU32>, Str>, mut receiver: Receiver<DbCommand>) {
+fn run_database(env: Env, tables: Tables, mut receiver: Receiver<DbCommand>) {
     loop {
         // Receive command
         let Some(command) = block_on(receiver.recv()) else {
@@ -141,22 +427,62 @@ fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, cids: HeedDatabas
         // Execute command
         match command {
             DbCommand::IndexGet { keys, sender } => {
-                let result = index_get(keys, &env, &index);
+                let result = index_get(keys, &env, &tables.index);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send index database read result: {e:?}") }
             },
             DbCommand::IndexPut { items, sender } => {
-                let result = index_put(&items, &env, &index);
+                let result = index_put(&items, &env, &tables.index);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send index database write result: {e:?}") }
             },
+            DbCommand::FiltersGet { keys, sender } => {
+                let result = filters_get(keys, &env, &tables.filters);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send filters database read result: {e:?}") }
+            },
+            DbCommand::FiltersPut { items, sender } => {
+                let result = filters_put(items, &env, &tables.filters);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send filters database write result: {e:?}") }
+            },
             DbCommand::PutCids { items, sender } => {
-                let result = put_cids(items, &env, &cids);
+                let result = put_cids(items, &env, &tables.cids);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send cids database write result: {e:?}") }
             },
+            DbCommand::PutAncestors { items, sender } => {
+                let result = put_ancestors(items, &env, &tables.ancestors);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send ancestors database write result: {e:?}") }
+            },
+            DbCommand::PutFolders { items, sender } => {
+                let result = put_folders(items, &env, &tables.folders);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send folders database write result: {e:?}") }
+            },
+            DbCommand::GetListed { cid, offset, limit, sender } => {
+                let result = get_listed(cid, offset, limit, &env, &tables.listed, &tables.listed_entries);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send listed database read result: {e:?}") }
+            },
+            DbCommand::PutListed { cid, offset, children, complete, sender } => {
+                let result = put_listed(cid, offset, children, complete, &env, &tables.listed, &tables.listed_entries);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send listed database write result: {e:?}") }
+            },
+            DbCommand::PutDocumentKeys { items, sender } => {
+                let result = put_document_keys(items, &env, &tables.document_keys);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send document keys database write result: {e:?}") }
+            },
+            DbCommand::RemoveCids { lcids, sender } => {
+                let result = remove_cids(&lcids, &env, &tables);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send cids database removal result: {e:?}") }
+            },
             DbCommand::ComputeFilter { sender } => {
-                let result = compute_filter(&env, &index);
+                let result = compute_filter(&env, &tables);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send filter computation result: {e:?}") }
             },
@@ -164,7 +490,7 @@ fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, cids: HeedDatabas
     }
 }
 
-pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCid, String>) {
+pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCid, String>, HashMap<LocalCid, HashMap<LocalCid, String>>, HashSet<LocalCid>) {
     trace!("Opening database at {}", config.database_path);
 
     // Open env
@@ -180,6 +506,12 @@ pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCi
     let mut wtxn = env.write_txn().expect("Failed to open write transaction for database creation");
     let index = env.create_database(&mut wtxn, Some("index")).expect("Failed to create index database");
     let cid_db: HeedDatabase<OwnedType<LEU32>, Str> = env.create_database(&mut wtxn, Some("cids")).expect("Failed to create cids database");
+    let ancestor_db: HeedDatabase<OwnedType<LEU32>, ByteSlice> = env.create_database(&mut wtxn, Some("ancestors")).expect("Failed to create ancestors database");
+    let folder_db: HeedDatabase<OwnedType<LEU32>, Unit> = env.create_database(&mut wtxn, Some("folders")).expect("Failed to create folders database");
+    let filter_db: HeedDatabase<Str, ByteSlice> = env.create_database(&mut wtxn, Some("filters")).expect("Failed to create filters database");
+    let listed_db: HeedDatabase<Str, OwnedType<LEU32>> = env.create_database(&mut wtxn, Some("listed_dirs")).expect("Failed to create listed directories database");
+    let listed_entries_db: HeedDatabase<Str, SerdeJson<ListedEntry>> = env.create_database(&mut wtxn, Some("listed_entries")).expect("Failed to create listed entries database");
+    let document_keys_db: HeedDatabase<OwnedType<LEU32>, SerdeJson<DocumentKeys>> = env.create_database(&mut wtxn, Some("document_keys")).expect("Failed to create document keys database");
     wtxn.commit().expect("Failed to commit write transaction for database creation");
 
     // Retrieve all cids
@@ -195,13 +527,20 @@ pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCi
         }
         cids.insert(LocalCid(lcid), cid.to_owned());
     }
+
+    // Retrieve the ancestor graph and folders
+    let db_ancestors = ancestor_db.iter(&rotxn).expect("Failed to iterate over ancestors database").filter_map(|a| a.ok());
+    let ancestors: HashMap<LocalCid, HashMap<LocalCid, String>> = db_ancestors.map(|(lcid, data)| (LocalCid(lcid.get()), decode_ancestors(data))).collect();
+    let db_folders = folder_db.iter(&rotxn).expect("Failed to iterate over folders database").filter_map(|f| f.ok());
+    let folders: HashSet<LocalCid> = db_folders.map(|(lcid, _)| LocalCid(lcid.get())).collect();
     drop(rotxn);
     if !cids.is_empty() {
-        debug!("{} documents retrieved from disk in {}ms", cids.len(), start.elapsed().as_millis());
+        debug!("{} cids, {} ancestor entries and {} folders retrieved from disk in {}ms", cids.len(), ancestors.len(), folders.len(), start.elapsed().as_millis());
     }
 
+    let tables = Tables { index, cids: cid_db, ancestors: ancestor_db, folders: folder_db, filters: filter_db, listed: listed_db, listed_entries: listed_entries_db, document_keys: document_keys_db };
     let (sender, receiver) = channel(200);    
-    std::thread::spawn(move || run_database(env, index, cid_db, receiver));
+    std::thread::spawn(move || run_database(env, tables, receiver));
 
-    (DbController{sender}, max+100_000 /* TODO: refine value */, cids)
+    (DbController{sender}, max+100_000 /* TODO: refine value */, cids, ancestors, folders)
 }
@@ -0,0 +1,117 @@
+//! Publication dates of documents, stored as days since the Unix epoch.
+//! The index only knows dates through [DATE_FILTERS] buckets, so that ranges can be matched with the kamilata filter too.
+//! A range is turned into the smallest set of years, months and days covering it.
+
+/// Names of the filters under which dates are indexed, from the largest bucket to the smallest
+pub const DATE_FILTERS: [&str; 3] = ["year", "month", "day"];
+
+/// Converts a date to the number of days since 1970-01-01
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Converts a number of days since 1970-01-01 to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses `YYYY`, `YYYY-MM` or `YYYY-MM-DD` into the first and last days of that period
+pub fn parse_date_period(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.split('-');
+    let year = parts.next().filter(|year| year.len() == 4)?.parse::<i64>().ok()?;
+    let month = parts.next().map(|month| month.parse::<u32>().ok().filter(|m| (1..=12).contains(m))).unwrap_or(Some(0))?;
+    let day = parts.next().map(|day| day.parse::<u32>().ok().filter(|d| month > 0 && (1..=days_in_month(year, month)).contains(d))).unwrap_or(Some(0))?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let (first, last) = match (month, day) {
+        (0, _) => (days_from_civil(year, 1, 1), days_from_civil(year, 12, 31)),
+        (month, 0) => (days_from_civil(year, month, 1), days_from_civil(year, month, days_in_month(year, month))),
+        (month, day) => (days_from_civil(year, month, day), days_from_civil(year, month, day)),
+    };
+    Some((u32::try_from(first).ok()?, u32::try_from(last).ok()?))
+}
+
+/// Parses a full date, optionally followed by a time, such as `2023-06-01` or `2023-06-01T12:00:00Z`
+pub fn parse_date(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let date = value.get(..10)?;
+    let time = &value[10..];
+    if !time.is_empty() && !time.starts_with(['T', 't', ' ']) {
+        return None;
+    }
+    match parse_date_period(date)? {
+        (first, last) if first == last => Some(first),
+        _ => None,
+    }
+}
+
+/// Converts a UnixFS modification time (in seconds) to a date
+pub fn date_from_timestamp(timestamp: u64) -> Option<u32> {
+    u32::try_from(timestamp / 86400).ok()
+}
+
+/// Returns the current date, in days since the Unix epoch
+pub fn today() -> u32 {
+    (crate::prelude::now() / 86400) as u32
+}
+
+/// Lists the buckets a date is indexed under
+pub fn date_filters(date: u32) -> Vec<(&'static str, String)> {
+    let (year, month, day) = civil_from_days(date as i64);
+    vec![
+        (DATE_FILTERS[0], format!("{year:04}")),
+        (DATE_FILTERS[1], format!("{year:04}-{month:02}")),
+        (DATE_FILTERS[2], format!("{year:04}-{month:02}-{day:02}")),
+    ]
+}
+
+/// Lists the smallest set of buckets covering all dates from `first` to `last`, both included.
+/// Ranges are cut at the end of the year following `today`, as there is no point looking for documents published later.
+pub fn date_range_filters(first: u32, last: u32, today: u32) -> Vec<(&'static str, String)> {
+    let (current_year, _, _) = civil_from_days(today as i64);
+    let last = (last as i64).min(days_from_civil(current_year + 1, 12, 31));
+
+    let mut filters = Vec::new();
+    let mut date = first as i64;
+    while date <= last {
+        let (year, month, day) = civil_from_days(date);
+        let end_of_year = days_from_civil(year, 12, 31);
+        let end_of_month = days_from_civil(year, month, days_in_month(year, month));
+        if month == 1 && day == 1 && end_of_year <= last {
+            filters.push((DATE_FILTERS[0], format!("{year:04}")));
+            date = end_of_year + 1;
+        } else if day == 1 && end_of_month <= last {
+            filters.push((DATE_FILTERS[1], format!("{year:04}-{month:02}")));
+            date = end_of_month + 1;
+        } else {
+            filters.push((DATE_FILTERS[2], format!("{year:04}-{month:02}-{day:02}")));
+            date += 1;
+        }
+    }
+    filters
+}
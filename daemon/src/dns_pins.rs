@@ -4,6 +4,10 @@ pub async fn manage_dns_pins(config: Arc<Args>) {
     if config.dns_pins.is_empty() {
         return;
     }
+    if config.source != SourceKind::Kubo {
+        error!("DNS pins require the kubo source. They will be ignored.");
+        return;
+    }
     if config.dns_pins.len() > 10 {
         warn!("You have a lot of DNS pins. Don't hesitate lowering the dns_pins_interval if you get rate limited by your DNS provider.")
     }
@@ -0,0 +1,110 @@
+use encoding_rs::{Encoding, UTF_8};
+use chardetng::EncodingDetector;
+use std::borrow::Cow;
+use super::*;
+
+/// Number of bytes scanned for a charset declaration, as browsers do
+const PRESCAN_LENGTH: usize = 1024;
+
+/// Reads the value of a `charset=` parameter, which may be quoted
+fn charset_value(s: &[u8]) -> Option<&[u8]> {
+    let start = s.iter().position(|b| !b.is_ascii_whitespace() && *b != b'=' && *b != b'"' && *b != b'\'')?;
+    let s = &s[start..];
+    let end = s.iter().position(|b| b.is_ascii_whitespace() || [b'"', b'\'', b';', b'>', b'/'].contains(b)).unwrap_or(s.len());
+    Some(&s[..end])
+}
+
+/// Finds the encoding declared by `<meta charset>` or `<meta http-equiv="Content-Type">` tags at the beginning of an HTML document
+fn declared_encoding(raw: &[u8]) -> Option<&'static Encoding> {
+    let start = raw[..raw.len().min(PRESCAN_LENGTH)].to_ascii_lowercase();
+    let mut rest = start.as_slice();
+    while let Some(i) = rest.windows(5).position(|w| w == b"<meta") {
+        let tag = &rest[i..];
+        let tag = &tag[..tag.iter().position(|b| *b == b'>').unwrap_or(tag.len())];
+        if let Some(j) = tag.windows(7).position(|w| w == b"charset") {
+            if let Some(encoding) = charset_value(&tag[j + 7..]).and_then(Encoding::for_label) {
+                // Declarations of UTF-16 can't be right as the declaration itself was readable as ASCII
+                return Some(encoding.output_encoding());
+            }
+        }
+        rest = &rest[i + 5..];
+    }
+    None
+}
+
+/// Decodes a textual document into a string.
+/// The encoding is found from the byte order mark, then from charset declarations of HTML documents, and is otherwise guessed from the content.
+/// Indexing and result generation must decode documents the same way so that peers agree on word counts.
+pub(super) fn decode_document(raw: &[u8], kind: DocumentKind) -> Cow<'_, str> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(raw) {
+        return encoding.decode_without_bom_handling(&raw[bom_length..]).0;
+    }
+
+    let declared = match kind {
+        DocumentKind::Html => declared_encoding(raw),
+        _ => None,
+    };
+    let encoding = declared.unwrap_or_else(|| {
+        if std::str::from_utf8(raw).is_ok() {
+            return UTF_8;
+        }
+        let mut detector = EncodingDetector::new();
+        detector.feed(raw, true);
+        detector.guess(None, true)
+    });
+
+    encoding.decode_without_bom_handling(raw).0
+}
+
+/// Returns the first bytes of a document, converted to UTF-8 if it starts with a byte order mark
+pub(super) fn utf8_start(raw: &[u8], length: usize) -> Cow<'_, [u8]> {
+    match Encoding::for_bom(raw) {
+        Some((encoding, bom_length)) => {
+            let end = raw.len().min(bom_length + length);
+            match encoding.decode_without_bom_handling(&raw[bom_length..end]).0 {
+                Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+                Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+            }
+        },
+        None => Cow::Borrowed(&raw[..raw.len().min(length)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom() {
+        assert_eq!(decode_document(b"\xef\xbb\xbfcaf\xc3\xa9", DocumentKind::Text), "café");
+        assert_eq!(decode_document(b"\xff\xfec\x00a\x00f\x00\xe9\x00", DocumentKind::Text), "café");
+        // Byte order marks win over declarations
+        assert_eq!(decode_document(b"\xef\xbb\xbf<meta charset=\"windows-1252\">caf\xc3\xa9", DocumentKind::Html), "<meta charset=\"windows-1252\">café");
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        let html = b"<html><head><meta charset='windows-1252'></head><body>caf\xe9</body></html>";
+        assert!(decode_document(html, DocumentKind::Html).contains("café"));
+
+        let html = b"<html><head><META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=iso-8859-1\"></head><body>caf\xe9</body></html>";
+        assert!(decode_document(html, DocumentKind::Html).contains("café"));
+
+        // A UTF-16 declaration readable as ASCII is wrong
+        let html = "<meta charset=\"utf-16\"><p>café</p>";
+        assert_eq!(decode_document(html.as_bytes(), DocumentKind::Html), html);
+
+        // Only HTML documents are searched for declarations
+        let markdown = "<meta charset=\"windows-1252\">\n\ncafé";
+        assert_eq!(decode_document(markdown.as_bytes(), DocumentKind::Markdown), markdown);
+    }
+
+    #[test]
+    fn test_detected_encoding() {
+        let (text, _, _) = encoding_rs::WINDOWS_1252.encode("Le café était déjà très chaud, mais la crème brûlée était délicieuse à côté de la fenêtre.");
+        assert_eq!(decode_document(&text, DocumentKind::Text), "Le café était déjà très chaud, mais la crème brûlée était délicieuse à côté de la fenêtre.");
+
+        let (text, _, _) = encoding_rs::SHIFT_JIS.encode("東京都の天気は晴れです。明日は雨が降るでしょう。");
+        assert_eq!(decode_document(&text, DocumentKind::Text), "東京都の天気は晴れです。明日は雨が降るでしょう。");
+    }
+}
@@ -0,0 +1,255 @@
+use scraper::{Selector, Html, ElementRef};
+use super::*;
+
+/// Collects the text of an element, leaving out scripts and styles, until there is enough to detect its language
+fn visible_text(el: ElementRef, text: &mut String) {
+    if ["script", "style"].contains(&el.value().name()) {
+        return;
+    }
+    for child in el.children() {
+        if text.len() >= MAX_DETECTION_LENGTH {
+            return;
+        }
+        match child.value() {
+            scraper::node::Node::Element(_) => {
+                let child_ref = ElementRef::wrap(child).expect("Child isn't an element");
+                visible_text(child_ref, text)
+            },
+            scraper::node::Node::Text(child_text) => {
+                text.push_str(child_text);
+                text.push(' ');
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Detects the language of a document from its text, using its lang attribute as a hint
+fn document_lang(document: &Html, body_el: Option<ElementRef>) -> String {
+    let html_selector = Selector::parse("html").expect("Invalid html selector");
+    let declared = document.select(&html_selector).next().and_then(|el| el.value().attr("lang"));
+    let mut text = String::new();
+    if let Some(body_el) = body_el {
+        visible_text(body_el, &mut text);
+    }
+    detect_language(&text, declared).unwrap_or(String::from("unknown"))
+}
+
+/// Returns true if the document asks not to be indexed with `<meta name="robots" content="noindex">`
+fn is_noindex(document: &Html) -> bool {
+    let robots_selector = Selector::parse("meta[name=robots i]").expect("Invalid robots selector");
+    document
+        .select(&robots_selector)
+        .filter_map(|el| el.value().attr("content"))
+        .flat_map(|content| content.split(','))
+        .any(|directive| ["noindex", "none"].contains(&directive.trim().to_lowercase().as_str()))
+}
+
+/// Finds when a document was published, from its metadata first, then from the first `<time>` element with a machine-readable date
+fn published_date(document: &Html) -> Option<u32> {
+    if let Some(date) = meta_content(document, &["article:published_time"]).and_then(|date| parse_date(&date)) {
+        return Some(date);
+    }
+    if let Some(date) = json_ld_date_published(document).and_then(|date| parse_date(&date)) {
+        return Some(date);
+    }
+    let microdata_selector = Selector::parse("[itemprop~=datePublished]").expect("Invalid datePublished selector");
+    let time_selector = Selector::parse("time[datetime]").expect("Invalid time selector");
+    document
+        .select(&microdata_selector)
+        .filter_map(|el| el.value().attr("content").or_else(|| el.value().attr("datetime")))
+        .chain(document.select(&time_selector).filter_map(|el| el.value().attr("datetime")))
+        .find_map(parse_date)
+}
+
+pub(super) fn inspect_document_html(raw: &str) -> Option<DocumentInspectionReport> {
+    let document = Html::parse_document(raw);
+    if is_noindex(&document) {
+        return None;
+    }
+    let mut filters = Vec::new();
+
+    // Get words
+    let body_selector = Selector::parse("body").expect("Invalid body selector");
+    let body_el = document.select(&body_selector).next();
+
+    fn list_words(el: ElementRef, words: &mut Vec<String>) {
+        match el.value().name() {
+            "script" | "style" => return,
+            "img" => words.extend(el.value().attr("alt").map(tokenize).unwrap_or_default()),
+            _ => (),
+        }
+        for child in el.children() {
+            match child.value() {
+                scraper::node::Node::Element(_) => {
+                    let child_ref = ElementRef::wrap(child).expect("Child isn't an element");
+                    list_words(child_ref, words)
+                },
+                scraper::node::Node::Text(text) => words.extend(tokenize(text)),
+                _ => (),
+            }
+        }
+
+    }
+
+    let mut words = Vec::new();
+    if let Some(body_el) = body_el {
+        list_words(body_el, &mut words);
+    }
+
+    // Get lang
+    let lang = document_lang(&document, body_el);
+    filters.push(("lang", lang));
+
+    // Get the types of structured data items
+    let mut schema_types = extract_structured_data(&document).iter().map(|item| item.schema_type()).collect::<Vec<_>>();
+    schema_types.sort();
+    schema_types.dedup();
+    filters.extend(schema_types.into_iter().map(|schema_type| (SCHEMA_FILTER, schema_type.to_owned())));
+
+    let mut report = DocumentInspectionReport { words, stems: Vec::new(), filters, date: None };
+    if let Some(date) = published_date(&document) {
+        report.set_date(date);
+    }
+    Some(report)
+}
+
+/// Reads the first non-empty meta tag among names or OpenGraph properties, in order of preference
+fn meta_content(document: &Html, keys: &[&str]) -> Option<String> {
+    let meta_selector = Selector::parse("meta[content]").expect("Invalid meta selector");
+    keys.iter().find_map(|key| {
+        document
+            .select(&meta_selector)
+            .filter(|el| el.value().attr("property") == Some(key) || el.value().attr("name") == Some(key))
+            .filter_map(|el| el.value().attr("content"))
+            .map(|content| content.trim())
+            .find(|content| !content.is_empty())
+            .map(|content| content.to_owned())
+    })
+}
+
+#[allow(clippy::question_mark)]
+pub(super) fn generate_result_html(raw: &str, query: &Query) -> Option<DocumentResult> {
+    let document = Html::parse_document(raw);
+    let body_selector = Selector::parse("body").expect("Invalid body selector");
+    let body_el = document.select(&body_selector).next();
+
+    // Get lang
+    let lang = document_lang(&document, body_el);
+
+    // Retrieve title
+    let title_selector = Selector::parse("title").expect("Invalid title selector");
+    let title_el = document.select(&title_selector).next();
+    let mut title = title_el.map(|el| el.text().collect::<Vec<_>>().join(" "));
+    if title.as_ref().map(|t| t.trim().is_empty()).unwrap_or(false) {
+        title = None;
+    }
+    if title.is_none() {
+        title = meta_content(&document, &["og:title", "twitter:title"]);
+    }
+
+    // Retrieve h1
+    let mut h1 = None;
+    if title.is_none() {
+        let h1_selector = Selector::parse("h1").expect("Invalid h1 selector");
+        let h1_el = document.select(&h1_selector).next();
+        h1 = h1_el.map(|el| el.text().collect::<Vec<_>>().join(" "));
+        if h1.as_ref().map(|t| t.trim().is_empty()).unwrap_or(false) {
+            h1 = None;
+        }
+    }
+    
+    if title.is_none() && h1.is_none() {
+        return None;
+    }
+
+    // Retrieve favicons
+    let favicon_selector = Selector::parse(r#"html>head>link[rel="icon"], html>head>link[rel="shortcut icon"], html>head>link[rel="apple-touch-icon"], html>head>link[rel="apple-touch-icon-precomposed"]"#).expect("Invalid icon selector");
+    let mut favicons = Vec::new();
+    for favicon_el in document.select(&favicon_selector) {
+        let Some(href) = favicon_el.value().attr("href").map(|href| href.to_string()) else {continue};
+        let Some(mime_type) = favicon_el.value().attr("type").map(|mime_type| mime_type.to_string()) else {continue};
+        let Some(sizes) = favicon_el.value().attr("sizes").map(|sizes| sizes.to_string()) else {continue};
+        favicons.push(FaviconDescriptor { href, mime_type, sizes });
+    }
+
+    // Retrieve description
+    let description_selector = Selector::parse("meta[name=description]").expect("Invalid description selector");
+    let description_el = document.select(&description_selector).next();
+    let mut description = description_el.and_then(|el| el.value().attr("content").map(|c| c.to_string()));
+    if description.as_ref().map(|d| d.trim().is_empty()).unwrap_or(true) {
+        description = meta_content(&document, &["og:description", "twitter:description"]);
+    }
+
+    // Retrieve OpenGraph and Twitter card metadata
+    let preview_image = meta_content(&document, &["og:image", "og:image:url", "og:image:secure_url", "twitter:image", "twitter:image:src"]);
+    let site_name = meta_content(&document, &["og:site_name"]);
+    let published = meta_content(&document, &["article:published_time"]);
+
+    // Retrieve the most relevant extract
+    let body = document.select(&Selector::parse("body").expect("Invalid body selector")).next()?;
+    let query_positive_terms = query.positive_terms();
+    let extract = best_extract(body.text(), &query_positive_terms);
+    
+    if description.is_none() && extract.is_none() {
+        return None;
+    }
+
+    // Retrieve images and videos
+    let media = extract_media(&document);
+
+    // Count words
+    // Only exact occurrences of terms are counted, even with stemming enabled, so that any peer can verify the counts
+    fn count_words(el: ElementRef, counter: &mut WordCounter, mut style: TextStyle) {
+        match el.value().name() {
+            "h1" => style.h1 = true,
+            "h2" => style.h2 = true,
+            "h3" => style.h3 = true,
+            "h4" => style.h4 = true,
+            "h5" => style.h5 = true,
+            "h6" => style.h6 = true,
+            "strong" => style.strong = true,
+            "em" => style.em = true,
+            "small" => style.small = true,
+            "s" => style.s = true,
+            "img" => if let Some(alt) = el.value().attr("alt") {
+                counter.add_text(alt, style)
+            },
+            "script" | "style" => return,
+            _ => (),
+        }
+        for child in el.children() {
+            match child.value() {
+                scraper::node::Node::Element(_) => {
+                    let child_ref = ElementRef::wrap(child).expect("Child isn't an element");
+                    count_words(child_ref, counter, style)
+                },
+                scraper::node::Node::Text(text) => counter.add_text(text, style),
+                _ => (),
+            }
+        }
+    }
+    let mut counter = WordCounter::new(&query_positive_terms, &lang);
+    count_words(body, &mut counter, TextStyle::default());
+    let (term_counts, word_count, common_words) = counter.finish();
+
+    Some(DocumentResult {
+        cid: String::new(),
+        paths: Vec::new(),
+        favicons,
+        title,
+        h1,
+        description,
+        extract,
+        preview_image,
+        site_name,
+        published,
+
+        media,
+        structured_data: extract_structured_data(&document),
+
+        term_counts,
+        word_count,
+        common_words,
+    })
+}
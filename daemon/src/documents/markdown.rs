@@ -0,0 +1,118 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag};
+use super::*;
+
+/// Text of a Markdown document, as pieces of text with their formatting
+struct MarkdownContent {
+    /// Text of the first heading
+    title: Option<String>,
+    /// Text of paragraphs, headings, list items and other blocks
+    blocks: Vec<String>,
+    spans: Vec<(String, TextStyle)>,
+}
+
+fn parse_markdown(raw: &str) -> MarkdownContent {
+    let mut title = None;
+    let mut in_first_heading = false;
+    let mut blocks = Vec::new();
+    let mut block = String::new();
+    let mut spans = Vec::new();
+    let mut style = TextStyle::default();
+
+    for event in Parser::new_ext(raw, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES) {
+        match event {
+            Event::Start(Tag::Heading(level, _, _)) => {
+                blocks.push(std::mem::take(&mut block));
+                match level {
+                    HeadingLevel::H1 => style.h1 = true,
+                    HeadingLevel::H2 => style.h2 = true,
+                    HeadingLevel::H3 => style.h3 = true,
+                    HeadingLevel::H4 => style.h4 = true,
+                    HeadingLevel::H5 => style.h5 = true,
+                    HeadingLevel::H6 => style.h6 = true,
+                }
+                in_first_heading = title.is_none();
+            },
+            Event::Start(Tag::Strong) => style.strong = true,
+            Event::Start(Tag::Emphasis) => style.em = true,
+            Event::Start(Tag::Strikethrough) => style.s = true,
+            Event::End(Tag::Strong) => style.strong = false,
+            Event::End(Tag::Emphasis) => style.em = false,
+            Event::End(Tag::Strikethrough) => style.s = false,
+            Event::End(Tag::Heading(_, _, _)) => {
+                if in_first_heading && !block.trim().is_empty() {
+                    title = Some(block.trim().to_owned());
+                }
+                in_first_heading = false;
+                style = TextStyle { strong: style.strong, em: style.em, s: style.s, ..TextStyle::default() };
+                blocks.push(std::mem::take(&mut block));
+            },
+            Event::End(Tag::Paragraph | Tag::Item | Tag::CodeBlock(_) | Tag::TableCell) => blocks.push(std::mem::take(&mut block)),
+            Event::Text(text) | Event::Code(text) => {
+                block.push_str(&text);
+                spans.push((text.into_string(), style));
+            },
+            Event::SoftBreak | Event::HardBreak => block.push(' '),
+            _ => (),
+        }
+    }
+    blocks.push(block);
+    blocks.retain(|block| !block.trim().is_empty());
+
+    MarkdownContent { title, blocks, spans }
+}
+
+pub(super) fn inspect_document_markdown(raw: &str) -> Option<DocumentInspectionReport> {
+    let content = parse_markdown(raw);
+    let mut filters = Vec::new();
+
+    // Get words
+    let words = content.spans.iter().flat_map(|(text, _)| tokenize(text)).collect::<Vec<_>>();
+
+    // Get lang
+    let lang = detect_language(&content.blocks.join("\n"), None).unwrap_or(String::from("unknown"));
+    filters.push(("lang", lang));
+
+    Some(DocumentInspectionReport { words, stems: Vec::new(), filters, date: None })
+}
+
+pub(super) fn generate_result_markdown(raw: &str, query: &Query, name: Option<&str>) -> Option<DocumentResult> {
+    let content = parse_markdown(raw);
+
+    // Get lang
+    let lang = detect_language(&content.blocks.join("\n"), None).unwrap_or(String::from("unknown"));
+
+    // Retrieve title, falling back to the file name
+    let title = content.title.clone().or_else(|| name.map(|name| name.to_owned()))?;
+
+    // Retrieve the most relevant extract
+    let query_positive_terms = query.positive_terms();
+    let fragments = content.blocks.iter().flat_map(|block| sentence_fragments(block)).collect::<Vec<_>>();
+    let extract = best_extract(fragments.iter().map(|fragment| fragment.as_str()), &query_positive_terms)?;
+
+    // Count words
+    let mut counter = WordCounter::new(&query_positive_terms, &lang);
+    for (text, style) in &content.spans {
+        counter.add_text(text, *style);
+    }
+    let (term_counts, word_count, common_words) = counter.finish();
+
+    Some(DocumentResult {
+        cid: String::new(),
+        paths: Vec::new(),
+        favicons: Vec::new(),
+        title: Some(title),
+        h1: None,
+        description: None,
+        extract: Some(extract),
+        preview_image: None,
+        site_name: None,
+        published: None,
+
+        media: Vec::new(),
+        structured_data: Vec::new(),
+
+        term_counts,
+        word_count,
+        common_words,
+    })
+}
@@ -0,0 +1,99 @@
+use scraper::{Selector, Html, ElementRef};
+use super::*;
+
+/// Only the first images and videos of a document are kept
+const MAX_MEDIA: usize = 20;
+/// Alt texts, captions and context are truncated to this size (in bytes)
+const MAX_MEDIA_TEXT_LENGTH: usize = 200;
+/// Images declared smaller than this (in pixels) are icons or spacers
+const MIN_IMAGE_SIZE: u32 = 32;
+/// Elements whose text is used as the context of the media they contain
+const CONTEXT_ELEMENTS: &[&str] = &["p", "li", "td", "dd", "blockquote", "section", "article", "div"];
+
+/// Returns the first candidate of a srcset attribute
+fn first_srcset_candidate(srcset: &str) -> Option<&str> {
+    srcset.split(',').next()?.split_whitespace().next()
+}
+
+fn element_text(el: ElementRef) -> String {
+    el.text().collect::<Vec<_>>().join(" ")
+}
+
+/// Finds the caption of the figure enclosing a media element
+fn caption(el: ElementRef) -> Option<String> {
+    let figure = el.ancestors().filter_map(ElementRef::wrap).find(|ancestor| ancestor.value().name() == "figure")?;
+    let figcaption_selector = Selector::parse("figcaption").expect("Invalid figcaption selector");
+    let figcaption = figure.select(&figcaption_selector).next()?;
+    limit_text(&element_text(figcaption), MAX_MEDIA_TEXT_LENGTH)
+}
+
+/// Finds the text of the closest block enclosing a media element
+fn context(el: ElementRef) -> Option<String> {
+    el.ancestors()
+        .filter_map(ElementRef::wrap)
+        .take_while(|ancestor| ancestor.value().name() != "body")
+        .filter(|ancestor| CONTEXT_ELEMENTS.contains(&ancestor.value().name()))
+        .find_map(|ancestor| limit_text(&element_text(ancestor), MAX_MEDIA_TEXT_LENGTH))
+}
+
+fn is_small(el: ElementRef) -> bool {
+    ["width", "height"].iter().any(|attr| {
+        el.value().attr(attr).and_then(|size| size.trim().trim_end_matches("px").parse::<u32>().ok()).map(|size| size < MIN_IMAGE_SIZE).unwrap_or(false)
+    })
+}
+
+fn image(el: ElementRef) -> Option<MediaDescriptor> {
+    if is_small(el) {
+        return None;
+    }
+    let picture_source = || {
+        let picture = el.parent().and_then(ElementRef::wrap).filter(|parent| parent.value().name() == "picture")?;
+        let source_selector = Selector::parse("source[srcset]").expect("Invalid source selector");
+        let source = picture.select(&source_selector).next()?;
+        first_srcset_candidate(source.value().attr("srcset")?)
+    };
+    let src = el.value().attr("src")
+        .or_else(|| el.value().attr("srcset").and_then(first_srcset_candidate))
+        .or_else(picture_source)?;
+    Some(MediaDescriptor {
+        kind: MediaKind::Image,
+        src: src.trim().to_owned(),
+        alt: el.value().attr("alt").and_then(|alt| limit_text(alt, MAX_MEDIA_TEXT_LENGTH)),
+        caption: caption(el),
+        context: context(el),
+    })
+}
+
+fn video(el: ElementRef) -> Option<MediaDescriptor> {
+    let source_selector = Selector::parse("source[src]").expect("Invalid source selector");
+    let src = el.value().attr("src").or_else(|| el.select(&source_selector).next()?.value().attr("src"))?;
+    let label = el.value().attr("aria-label").or_else(|| el.value().attr("title"));
+    Some(MediaDescriptor {
+        kind: MediaKind::Video,
+        src: src.trim().to_owned(),
+        alt: label.and_then(|label| limit_text(label, MAX_MEDIA_TEXT_LENGTH)),
+        caption: caption(el),
+        context: context(el),
+    })
+}
+
+/// Lists the images and videos of a document, with unresolved sources
+pub(super) fn extract_media(document: &Html) -> Vec<MediaDescriptor> {
+    let media_selector = Selector::parse("body img, body video").expect("Invalid media selector");
+    let mut media = Vec::new();
+    for el in document.select(&media_selector) {
+        let item = match el.value().name() {
+            "img" => image(el),
+            _ => video(el),
+        };
+        let Some(item) = item else { continue };
+        if item.src.is_empty() || media.iter().any(|other: &MediaDescriptor| other.src == item.src) {
+            continue;
+        }
+        media.push(item);
+        if media.len() >= MAX_MEDIA {
+            break;
+        }
+    }
+    media
+}
@@ -0,0 +1,286 @@
+use crate::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+mod encoding;
+mod html;
+mod markdown;
+mod media;
+mod pdf;
+mod schema;
+mod text;
+use encoding::*;
+use html::*;
+use markdown::*;
+use media::*;
+use pdf::*;
+use schema::*;
+use text::*;
+
+/// Number of bytes looked at to find the type of a document
+const SNIFF_LENGTH: usize = 1024;
+/// Signatures of common binary formats that can't be indexed
+const BINARY_SIGNATURES: &[&[u8]] = &[
+    b"\x89PNG", b"\xFF\xD8\xFF", b"GIF8", b"RIFF", b"PK\x03\x04", b"\x1F\x8B", b"\x7FELF",
+    b"OggS", b"ID3", b"fLaC", b"wOFF", b"wOF2", b"\x00asm",
+];
+
+/// Fragments longer than this (in bytes) can't be used as extracts
+const MAX_EXTRACT_LENGTH: usize = 350;
+/// Fragments shorter than this (in bytes) can't be used as extracts
+const MIN_EXTRACT_LENGTH: usize = 50;
+
+pub struct DocumentInspectionReport {
+    pub words: Vec<String>,
+    /// Index keys of the stems of words, empty unless stemming is enabled
+    pub stems: Vec<String>,
+    /// Filters the document is indexed under, as (name, value). Some names, like `schema`, can have several values.
+    pub filters: Vec<(&'static str, String)>,
+    /// Publication date, in days since the Unix epoch
+    pub date: Option<u32>,
+}
+
+impl DocumentInspectionReport {
+    /// Sets the publication date, along with the filters it is indexed under
+    pub fn set_date(&mut self, date: u32) {
+        self.date = Some(date);
+        self.filters.extend(date_filters(date));
+    }
+}
+
+/// Formats of documents that can be indexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Html,
+    Markdown,
+    Text,
+    Pdf,
+}
+
+impl DocumentKind {
+    /// Guesses the kind of a document from its file name
+    pub fn from_name(name: &str) -> Option<DocumentKind> {
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "html" | "htm" => Some(DocumentKind::Html),
+            "md" | "markdown" => Some(DocumentKind::Markdown),
+            "txt" => Some(DocumentKind::Text),
+            "pdf" => Some(DocumentKind::Pdf),
+            _ => None,
+        }
+    }
+
+    /// Returns true for files that are likely documents, which are indexed first.
+    /// Files without extension are often web pages.
+    pub fn is_prioritized(name: &str) -> bool {
+        DocumentKind::from_name(name).is_some() || !name.contains('.')
+    }
+
+    /// Finds the kind of a document from its first bytes.
+    /// The file name is only used as a hint when the content is ambiguous, such as Markdown which looks like plain text.
+    /// Other text files are only indexed as plain text if their name suggests so, so that scripts, stylesheets or data files are left out.
+    pub fn sniff(raw: &[u8], name: Option<&str>) -> Option<DocumentKind> {
+        let start = utf8_start(raw, SNIFF_LENGTH);
+        let first_non_space = start.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(start.len());
+        let start = &start[first_non_space..];
+
+        if start.starts_with(b"%PDF-") {
+            return Some(DocumentKind::Pdf);
+        }
+        if start.contains(&0) || BINARY_SIGNATURES.iter().any(|signature| start.starts_with(signature)) {
+            return None;
+        }
+
+        let hint = name.and_then(DocumentKind::from_name);
+        let start = String::from_utf8_lossy(start).to_lowercase();
+        if start.starts_with('<') {
+            let has_html_tags = ["<!doctype html", "<html", "<head", "<body"].iter().any(|tag| start.contains(tag));
+            if has_html_tags || hint == Some(DocumentKind::Html) {
+                return Some(DocumentKind::Html);
+            }
+            if start.starts_with("<?xml") || start.starts_with("<svg") {
+                return None;
+            }
+        }
+
+        match (hint, name) {
+            (Some(DocumentKind::Markdown), _) => Some(DocumentKind::Markdown),
+            (Some(DocumentKind::Html), _) => Some(DocumentKind::Html),
+            (Some(DocumentKind::Text), _) | (_, None) => Some(DocumentKind::Text),
+            (_, Some(name)) if !name.contains('.') => Some(DocumentKind::Text),
+            _ => None,
+        }
+    }
+
+    /// Returns the MIME type of documents of this kind, used for the `mime` filter
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            DocumentKind::Html => "text/html",
+            DocumentKind::Markdown => "text/markdown",
+            DocumentKind::Text => "text/plain",
+            DocumentKind::Pdf => "application/pdf",
+        }
+    }
+}
+
+/// Formatting of a piece of text, which determines how much its words weigh
+#[derive(Debug, Clone, Copy, Default)]
+struct TextStyle {
+    h1: bool,
+    h2: bool,
+    h3: bool,
+    h4: bool,
+    h5: bool,
+    h6: bool,
+    strong: bool,
+    em: bool,
+    small: bool,
+    s: bool,
+}
+
+/// Counts the words of a document and the occurrences of query terms, taking their formatting into account
+struct WordCounter<'a> {
+    query_positive_terms: &'a [&'a String],
+    common_words: Option<&'static [&'static str]>,
+    common_words_bytes: usize,
+    uncommon_words_bytes: usize,
+    term_counts: Vec<WordCount>,
+    word_count: WordCount,
+}
+
+impl<'a> WordCounter<'a> {
+    fn new(query_positive_terms: &'a [&'a String], lang: &str) -> WordCounter<'a> {
+        WordCounter {
+            query_positive_terms,
+            common_words: word_lists::common_words(lang),
+            common_words_bytes: 0,
+            uncommon_words_bytes: 0,
+            term_counts: query_positive_terms.iter().map(|_| WordCount::default()).collect(),
+            word_count: WordCount::default(),
+        }
+    }
+
+    fn add_text(&mut self, text: &str, style: TextStyle) {
+        let TextStyle { h1, h2, h3, h4, h5, h6, strong, em, small, s } = style;
+        for word in tokenize(text) {
+            if let Some(common_words) = self.common_words {
+                if common_words.sorted_contains(&word) {
+                    self.common_words_bytes += word.len();
+                } else {
+                    self.uncommon_words_bytes += word.len();
+                }
+            }
+            if let Some(i) = self.query_positive_terms.iter().position(|q| *q == &word) {
+                let term_count = self.term_counts.get_mut(i).expect("term_counts not initialized properly");
+                term_count.add(h1, h2, h3, h4, h5, h6, strong, em, small, s)
+            }
+            self.word_count.add(h1, h2, h3, h4, h5, h6, strong, em, small, s);
+        }
+    }
+
+    /// Returns the term counts, the word count and the share of common words
+    fn finish(self) -> (Vec<WordCount>, WordCount, Option<f64>) {
+        let common_words = self.common_words.map(|_| self.common_words_bytes as f64 / (self.common_words_bytes + self.uncommon_words_bytes) as f64);
+        (self.term_counts, self.word_count, common_words)
+    }
+}
+
+fn extract_score(extract: &str, query_positive_terms: &[&String]) -> usize {
+    let mut score = 0;
+    let mut extract_words = tokenize(extract);
+    if extract_words.is_empty() {
+        return 0;
+    }
+    let first_word = extract_words.remove(0);
+    if query_positive_terms.contains(&&first_word) {
+        score += 4;
+    }
+    for query_positive_term in query_positive_terms {
+        if extract_words.contains(query_positive_term) {
+            score += 1;
+        }
+    }
+    score
+}
+
+/// Retrieves the most relevant extract among fragments of text
+fn best_extract<'a>(fragments: impl IntoIterator<Item = &'a str>, query_positive_terms: &[&String]) -> Option<String> {
+    let mut best_extract = "";
+    let mut best_extract_score = 0;
+    for fragment in fragments {
+        if fragment.len() >= MAX_EXTRACT_LENGTH || fragment.len() <= MIN_EXTRACT_LENGTH {
+            continue;
+        }
+        let score = extract_score(fragment, query_positive_terms);
+        if score > best_extract_score {
+            best_extract_score = score;
+            best_extract = fragment;
+        }
+    }
+    match best_extract_score > 0 {
+        true => Some(best_extract.to_string()),
+        false => None,
+    }
+}
+
+/// Collapses the whitespace of a text and truncates it at a character boundary
+fn limit_text(text: &str, max_length: usize) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    let mut end = text.len().min(max_length);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(text[..end].to_owned())
+}
+
+/// Splits a block of text into fragments that can be used as extracts, by grouping its sentences
+fn sentence_fragments(block: &str) -> Vec<String> {
+    let mut fragments = Vec::new();
+    let mut fragment = String::new();
+    for sentence in block.split_sentence_bounds() {
+        if !fragment.is_empty() && fragment.len() + sentence.len() >= MAX_EXTRACT_LENGTH {
+            fragments.push(fragment.trim().to_owned());
+            fragment.clear();
+        }
+        fragment.push_str(sentence);
+    }
+    if !fragment.trim().is_empty() {
+        fragments.push(fragment.trim().to_owned());
+    }
+    fragments
+}
+
+pub fn inspect_document(raw: Vec<u8>, name: &str, config: &Args) -> Option<DocumentInspectionReport> {
+    let kind = DocumentKind::sniff(&raw, Some(name))?;
+    let mut report = match kind {
+        DocumentKind::Html => inspect_document_html(&decode_document(&raw, kind))?,
+        DocumentKind::Markdown => inspect_document_markdown(&decode_document(&raw, kind))?,
+        DocumentKind::Text => inspect_document_text(&decode_document(&raw, kind))?,
+        DocumentKind::Pdf => inspect_document_pdf(&raw)?,
+    };
+    report.filters.push(("mime", kind.mime_type().to_owned()));
+    if config.stemming {
+        if let Some(stemmer) = report.filters.iter().find(|(name, _)| *name == "lang").and_then(|(_, lang)| stemmer(lang)) {
+            report.stems = report.words.iter().map(|word| stem_key(stemmer, word)).collect();
+        }
+    }
+
+    Some(report)
+}
+
+pub fn generate_result(raw: Vec<u8>, cid: String, query: &Query, paths: Vec<Vec<String>>) -> Option<DocumentResult> {
+    let name = paths.iter().find_map(|path| path.last()).map(|name| name.as_str());
+    let kind = DocumentKind::sniff(&raw, name)?;
+    let mut result = match kind {
+        DocumentKind::Html => generate_result_html(&decode_document(&raw, kind), query)?,
+        DocumentKind::Markdown => generate_result_markdown(&decode_document(&raw, kind), query, name)?,
+        DocumentKind::Text => generate_result_text(&decode_document(&raw, kind), query, name)?,
+        DocumentKind::Pdf => generate_result_pdf(&raw, query, name)?,
+    };
+    result.cid = cid;
+    result.paths = paths;
+
+    Some(result)
+}
@@ -0,0 +1,279 @@
+use pdf_extract::{decode_text_string, output_doc_page, Dictionary, Document, PlainTextOutput};
+use super::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst};
+
+/// PDFs larger than this (in bytes) are not parsed
+const MAX_PDF_SIZE: usize = 10_000_000;
+/// Only the first pages of PDFs are read
+const MAX_PDF_PAGES: usize = 300;
+/// Reading pages stops after this time, keeping the text read so far
+const PDF_TIME_LIMIT: Duration = Duration::from_secs(10);
+/// Parsing is given up on after this time, as malformed documents can keep the parser busy before reaching any page
+const PDF_PARSE_TIMEOUT: Duration = Duration::from_secs(20);
+/// Lines longer than this (in characters) are not used as titles
+const MAX_TITLE_LENGTH: usize = 100;
+/// At most this many PDFs are parsed at once, including those whose parsing timed out
+const MAX_PDF_PARSERS: usize = 4;
+
+/// Number of parser threads running
+static RUNNING_PDF_PARSERS: AtomicUsize = AtomicUsize::new(0);
+/// Number of parser threads that timed out and are still running
+static STALLED_PDF_PARSERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Held by a parser thread until it finishes, so that parser threads can't pile up
+struct PdfParserSlot {
+    /// Set by whichever of the parser thread and the caller gives up on the other first
+    abandoned: Arc<AtomicBool>,
+}
+
+impl PdfParserSlot {
+    /// Returns `None` if all slots are taken, or if a timed-out parser is still running
+    fn acquire() -> Option<PdfParserSlot> {
+        if STALLED_PDF_PARSERS.load(SeqCst) > 0 {
+            return None;
+        }
+        RUNNING_PDF_PARSERS.fetch_update(SeqCst, SeqCst, |running| (running < MAX_PDF_PARSERS).then_some(running + 1)).ok()?;
+        Some(PdfParserSlot { abandoned: Arc::new(AtomicBool::new(false)) })
+    }
+
+    /// Called by the caller once it stopped waiting for the parser thread
+    fn mark_stalled(abandoned: &AtomicBool) {
+        STALLED_PDF_PARSERS.fetch_add(1, SeqCst);
+        if abandoned.swap(true, SeqCst) {
+            // The thread finished in the meantime
+            STALLED_PDF_PARSERS.fetch_sub(1, SeqCst);
+        }
+    }
+}
+
+impl Drop for PdfParserSlot {
+    fn drop(&mut self) {
+        if self.abandoned.swap(true, SeqCst) {
+            STALLED_PDF_PARSERS.fetch_sub(1, SeqCst);
+        }
+        RUNNING_PDF_PARSERS.fetch_sub(1, SeqCst);
+    }
+}
+
+struct PdfContent {
+    title: Option<String>,
+    author: Option<String>,
+    subject: Option<String>,
+    /// Text of each page
+    pages: Vec<String>,
+}
+
+/// Reads a text entry of the document information dictionary
+fn info_string(doc: &Document, info: &Dictionary, key: &[u8]) -> Option<String> {
+    let (_, value) = doc.dereference(info.get(key).ok()?).ok()?;
+    let value = decode_text_string(value).ok()?;
+    let value = value.trim();
+    match value.is_empty() {
+        true => None,
+        false => Some(value.to_owned()),
+    }
+}
+
+fn parse_pdf_unchecked(raw: &[u8]) -> Option<PdfContent> {
+    let mut doc = Document::load_mem(raw).ok()?;
+    if doc.is_encrypted() {
+        // Documents are often encrypted with an empty password, only to restrict what readers can do
+        doc.decrypt("").ok()?;
+    }
+
+    // Read metadata
+    let info = doc.trailer.get(b"Info").ok().and_then(|info| doc.dereference(info).ok()).and_then(|(_, info)| info.as_dict().ok());
+    let title = info.and_then(|info| info_string(&doc, info, b"Title"));
+    let author = info.and_then(|info| info_string(&doc, info, b"Author"));
+    let subject = info.and_then(|info| info_string(&doc, info, b"Subject"));
+
+    // Read pages
+    let start = Instant::now();
+    let mut pages = Vec::new();
+    for page_num in doc.get_pages().into_keys().take(MAX_PDF_PAGES) {
+        if start.elapsed() > PDF_TIME_LIMIT {
+            debug!("Stopped reading PDF after {} pages as it took too long", pages.len());
+            break;
+        }
+        let mut text = String::new();
+        let result = output_doc_page(&doc, &mut PlainTextOutput::new(&mut text), page_num);
+        if result.is_err() {
+            text.clear();
+        }
+        pages.push(text);
+    }
+
+    Some(PdfContent { title, author, subject, pages })
+}
+
+fn parse_pdf(raw: &[u8]) -> Option<PdfContent> {
+    if raw.len() > MAX_PDF_SIZE || !raw.starts_with(b"%PDF") {
+        return None;
+    }
+
+    // Parsing happens on its own thread so that it can't block indexing for long.
+    // A thread that times out is left to finish on its own, and its result is dropped.
+    // New documents are skipped until it finishes, as it could be stuck for a long time.
+    let Some(slot) = PdfParserSlot::acquire() else {
+        debug!("Skipped parsing PDF as parsers are busy or stuck");
+        return None;
+    };
+    let abandoned = Arc::clone(&slot.abandoned);
+    let raw = raw.to_vec();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::Builder::new().name(String::from("pdf-parser")).spawn(move || {
+        let _slot = slot;
+        // The parser panics on some malformed documents
+        let content = std::panic::catch_unwind(|| parse_pdf_unchecked(&raw)).ok().flatten();
+        let _ = sender.send(content);
+    }).ok()?;
+    match receiver.recv_timeout(PDF_PARSE_TIMEOUT) {
+        Ok(content) => content,
+        Err(_) => {
+            PdfParserSlot::mark_stalled(&abandoned);
+            warn!("Gave up parsing PDF as it took too long");
+            None
+        }
+    }
+}
+
+pub(super) fn inspect_document_pdf(raw: &[u8]) -> Option<DocumentInspectionReport> {
+    let content = parse_pdf(raw)?;
+    let mut filters = Vec::new();
+
+    // Get words
+    let mut words = Vec::new();
+    for metadata in [&content.title, &content.author, &content.subject].into_iter().flatten() {
+        words.extend(tokenize(metadata));
+    }
+    for page in &content.pages {
+        words.extend(tokenize(page));
+    }
+
+    // Get lang
+    let lang = detect_language(&content.pages.join("\n"), None).unwrap_or(String::from("unknown"));
+    filters.push(("lang", lang));
+
+    Some(DocumentInspectionReport { words, stems: Vec::new(), filters, date: None })
+}
+
+pub(super) fn generate_result_pdf(raw: &[u8], query: &Query, name: Option<&str>) -> Option<DocumentResult> {
+    let content = parse_pdf(raw)?;
+
+    // Get lang
+    let lang = detect_language(&content.pages.join("\n"), None).unwrap_or(String::from("unknown"));
+
+    // Retrieve title, falling back to the first line and then to the file name
+    let first_line = content.pages.iter().flat_map(|page| page.lines()).map(|line| line.trim()).find(|line| !line.is_empty());
+    let title = match (content.title, first_line) {
+        (Some(title), _) => title,
+        (None, Some(first_line)) if first_line.chars().count() <= MAX_TITLE_LENGTH => first_line.to_owned(),
+        _ => name?.to_owned(),
+    };
+
+    // Retrieve description
+    let description = match (content.subject, content.author) {
+        (Some(subject), Some(author)) => Some(format!("{subject} — {author}")),
+        (subject, author) => subject.or(author),
+    };
+
+    // Retrieve the most relevant extract, along with the page it is on
+    let query_positive_terms = query.positive_terms();
+    let fragments = content.pages
+        .iter()
+        .enumerate()
+        .flat_map(|(i, page)| paragraphs(page).into_iter().flat_map(|paragraph| sentence_fragments(&paragraph)).map(move |fragment| (i + 1, fragment)))
+        .collect::<Vec<_>>();
+    let extract = best_extract(fragments.iter().map(|(_, fragment)| fragment.as_str()), &query_positive_terms);
+    let extract = extract.and_then(|extract| {
+        let (page, _) = fragments.iter().find(|(_, fragment)| *fragment == extract)?;
+        Some(format!("[p. {page}] {extract}"))
+    });
+
+    if description.is_none() && extract.is_none() {
+        return None;
+    }
+
+    // Count words
+    let mut counter = WordCounter::new(&query_positive_terms, &lang);
+    for page in &content.pages {
+        counter.add_text(page, TextStyle::default());
+    }
+    let (term_counts, word_count, common_words) = counter.finish();
+
+    Some(DocumentResult {
+        cid: String::new(),
+        paths: Vec::new(),
+        favicons: Vec::new(),
+        title: Some(title),
+        h1: None,
+        description,
+        extract,
+        preview_image: None,
+        site_name: None,
+        published: None,
+
+        media: Vec::new(),
+        structured_data: Vec::new(),
+
+        term_counts,
+        word_count,
+        common_words,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a one-page PDF with the given text and title
+    fn build_pdf(title: &str, text: &str) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 720 Td ({text}) Tj ET");
+        let objects = [
+            String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+            String::from("<< /Type /Pages /Kids [3 0 R] /Count 1 >>"),
+            String::from("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>"),
+            format!("<< /Length {} >>\nstream\n{content}\nendstream", content.len()),
+            String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+            format!("<< /Title ({title}) >>"),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n{object}\nendobj\n", i + 1).bytes());
+        }
+        let xref_offset = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{offset:010} 00000 n \n").bytes());
+        }
+        pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n", objects.len() + 1).bytes());
+        pdf
+    }
+
+    #[test]
+    fn test_parse_pdf() {
+        let pdf = build_pdf("Test document", "Hello world");
+        let content = parse_pdf(&pdf).expect("Failed to parse PDF");
+        assert_eq!(content.title.as_deref(), Some("Test document"));
+        assert_eq!(content.pages.len(), 1);
+        assert!(content.pages[0].contains("Hello world"));
+    }
+
+    #[test]
+    fn test_malformed_pdfs() {
+        assert!(parse_pdf(b"%PDF-1.4\n").is_none());
+        assert!(parse_pdf(b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R").is_none());
+
+        let mut garbage = b"%PDF-1.5\n".to_vec();
+        garbage.extend((0..100_000u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8));
+        assert!(parse_pdf(&garbage).is_none());
+
+        // Truncated documents must neither panic nor hang, whatever can be read from them
+        let pdf = build_pdf("Test document", "Hello world");
+        for len in [pdf.len() / 4, pdf.len() / 2, pdf.len() - 30] {
+            let _ = parse_pdf(&pdf[..len]);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use scraper::{Selector, Html, ElementRef};
+use serde_json::{Map, Value};
+use super::*;
+
+/// Follows a path of properties, taking the first element of arrays along the way
+fn get<'a>(mut value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    for key in path {
+        if let Value::Array(values) = value {
+            value = values.first()?;
+        }
+        value = value.get(key)?;
+    }
+    match value {
+        Value::Array(values) => values.first(),
+        value => Some(value),
+    }
+}
+
+/// Reads a property as text, using the name of nested items such as authors
+fn text(value: &Value, path: &[&str]) -> Option<String> {
+    match get(value, path)? {
+        Value::String(s) => limit_text(s, MAX_STRUCTURED_TEXT_LENGTH),
+        Value::Number(n) => Some(n.to_string()),
+        nested @ Value::Object(_) => text(nested, &["name"]).or_else(|| text(nested, &["@value"])),
+        _ => None,
+    }
+}
+
+/// Reads a property as a URL, which can also be given as a nested ImageObject
+fn url(value: &Value, path: &[&str]) -> Option<String> {
+    match get(value, path)? {
+        Value::String(s) => limit_text(s, MAX_STRUCTURED_TEXT_LENGTH),
+        nested @ Value::Object(_) => url(nested, &["url"]).or_else(|| url(nested, &["contentUrl"])),
+        _ => None,
+    }
+}
+
+fn number(value: &Value, path: &[&str]) -> Option<f64> {
+    match get(value, path)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().replace(',', ".").parse().ok(),
+        _ => None,
+    }.filter(|n: &f64| n.is_finite())
+}
+
+/// Lists the types of an item, without their schema.org prefix
+fn types(value: &Value) -> Vec<&str> {
+    let types = match value.get("@type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(|ty| ty.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    types.into_iter().map(|ty| ty.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or(ty)).collect()
+}
+
+/// Converts a JSON-LD item to one of the supported types
+fn from_json_ld(value: &Value) -> Option<StructuredData> {
+    for ty in types(value) {
+        let item = match ty {
+            "Article" | "NewsArticle" | "BlogPosting" | "TechArticle" | "ScholarlyArticle" | "Report" => StructuredData::Article(ArticleData {
+                headline: text(value, &["headline"]).or_else(|| text(value, &["name"]))?,
+                description: text(value, &["description"]),
+                author: text(value, &["author"]),
+                date_published: text(value, &["datePublished"]),
+                image: url(value, &["image"]),
+            }),
+            "Product" => StructuredData::Product(ProductData {
+                name: text(value, &["name"])?,
+                description: text(value, &["description"]),
+                brand: text(value, &["brand"]),
+                image: url(value, &["image"]),
+                price: text(value, &["offers", "price"]).or_else(|| text(value, &["offers", "lowPrice"])),
+                price_currency: text(value, &["offers", "priceCurrency"]),
+                rating: number(value, &["aggregateRating", "ratingValue"]),
+            }),
+            "Recipe" => StructuredData::Recipe(RecipeData {
+                name: text(value, &["name"])?,
+                description: text(value, &["description"]),
+                author: text(value, &["author"]),
+                image: url(value, &["image"]),
+                total_time: text(value, &["totalTime"]),
+                recipe_yield: text(value, &["recipeYield"]),
+                rating: number(value, &["aggregateRating", "ratingValue"]),
+            }),
+            "Person" => StructuredData::Person(PersonData {
+                name: text(value, &["name"])?,
+                job_title: text(value, &["jobTitle"]),
+                image: url(value, &["image"]),
+                url: url(value, &["url"]),
+            }),
+            "Organization" | "Corporation" | "NGO" | "LocalBusiness" | "EducationalOrganization" => StructuredData::Organization(OrganizationData {
+                name: text(value, &["name"])?,
+                description: text(value, &["description"]),
+                logo: url(value, &["logo"]),
+                url: url(value, &["url"]),
+            }),
+            ty if ty == "Event" || ty.ends_with("Event") => StructuredData::Event(EventData {
+                name: text(value, &["name"])?,
+                description: text(value, &["description"]),
+                start_date: text(value, &["startDate"]),
+                end_date: text(value, &["endDate"]),
+                location: text(value, &["location"]),
+            }),
+            _ => continue,
+        };
+        return Some(item);
+    }
+    None
+}
+
+/// Lists the items of a JSON-LD block, which can be a single item, an array or a graph
+fn json_ld_items(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(values) => values.into_iter().flat_map(json_ld_items).collect(),
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => json_ld_items(graph),
+            None => vec![Value::Object(object)],
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Finds the publication date declared by the first JSON-LD item that has one
+pub(super) fn json_ld_date_published(document: &Html) -> Option<String> {
+    let json_ld_selector = Selector::parse(r#"script[type="application/ld+json"]"#).expect("Invalid JSON-LD selector");
+    document
+        .select(&json_ld_selector)
+        .filter_map(|el| serde_json::from_str::<Value>(&el.text().collect::<String>()).ok())
+        .flat_map(json_ld_items)
+        .find_map(|item| text(&item, &["datePublished"]))
+}
+
+/// Reads the value of a microdata property from the element carrying it
+fn microdata_value(el: ElementRef) -> Value {
+    if el.value().attr("itemscope").is_some() {
+        return microdata_item(el);
+    }
+    let attr = match el.value().name() {
+        "meta" => "content",
+        "a" | "link" | "area" => "href",
+        "img" | "audio" | "video" | "source" | "iframe" | "embed" => "src",
+        "object" => "data",
+        "time" => "datetime",
+        "data" | "meter" => "value",
+        _ => "",
+    };
+    match el.value().attr(attr) {
+        Some(value) => Value::String(value.to_owned()),
+        None => Value::String(el.text().collect::<Vec<_>>().join(" ")),
+    }
+}
+
+/// Collects the properties of an item, without going into nested items
+fn collect_microdata_properties(el: ElementRef, properties: &mut Map<String, Value>) {
+    for child in el.children() {
+        let Some(child) = ElementRef::wrap(child) else { continue };
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = microdata_value(child);
+            for name in names.split_whitespace() {
+                properties.entry(name).or_insert_with(|| value.clone());
+            }
+        }
+        if child.value().attr("itemscope").is_none() {
+            collect_microdata_properties(child, properties);
+        }
+    }
+}
+
+/// Converts a microdata item to the equivalent JSON-LD
+fn microdata_item(el: ElementRef) -> Value {
+    let mut properties = Map::new();
+    if let Some(ty) = el.value().attr("itemtype") {
+        let types = ty.split_whitespace().map(|ty| Value::String(ty.to_owned())).collect();
+        properties.insert(String::from("@type"), Value::Array(types));
+    }
+    collect_microdata_properties(el, &mut properties);
+    Value::Object(properties)
+}
+
+/// Extracts schema.org items from JSON-LD blocks, then from microdata
+pub(super) fn extract_structured_data(document: &Html) -> Vec<StructuredData> {
+    let mut items = Vec::new();
+
+    let json_ld_selector = Selector::parse(r#"script[type="application/ld+json"]"#).expect("Invalid JSON-LD selector");
+    for el in document.select(&json_ld_selector) {
+        let Ok(value) = serde_json::from_str::<Value>(&el.text().collect::<String>()) else { continue };
+        items.extend(json_ld_items(value).iter().filter_map(from_json_ld));
+    }
+
+    let microdata_selector = Selector::parse("[itemscope][itemtype]:not([itemprop])").expect("Invalid microdata selector");
+    for el in document.select(&microdata_selector) {
+        items.extend(from_json_ld(&microdata_item(el)));
+    }
+
+    items.dedup();
+    items.truncate(MAX_STRUCTURED_DATA_ITEMS);
+    while items.iter().map(|item| item.size()).sum::<usize>() > MAX_STRUCTURED_DATA_SIZE {
+        items.pop();
+    }
+    items
+}
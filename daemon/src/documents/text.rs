@@ -0,0 +1,82 @@
+use super::*;
+
+/// Lines longer than this (in characters) are not used as titles
+const MAX_TITLE_LENGTH: usize = 100;
+
+/// Splits plain text into paragraphs, which are separated by blank lines
+pub(super) fn paragraphs(raw: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(std::mem::take(&mut paragraph));
+            }
+            continue;
+        }
+        if !paragraph.is_empty() {
+            paragraph.push(' ');
+        }
+        paragraph.push_str(line);
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    paragraphs
+}
+
+pub(super) fn inspect_document_text(raw: &str) -> Option<DocumentInspectionReport> {
+    let mut filters = Vec::new();
+
+    // Get words
+    let words = tokenize(raw);
+
+    // Get lang
+    let lang = detect_language(raw, None).unwrap_or(String::from("unknown"));
+    filters.push(("lang", lang));
+
+    Some(DocumentInspectionReport { words, stems: Vec::new(), filters, date: None })
+}
+
+pub(super) fn generate_result_text(raw: &str, query: &Query, name: Option<&str>) -> Option<DocumentResult> {
+    // Get lang
+    let lang = detect_language(raw, None).unwrap_or(String::from("unknown"));
+
+    // Retrieve title, which is the first line if it is short enough, or the file name
+    let first_line = raw.lines().map(|line| line.trim()).find(|line| !line.is_empty());
+    let title = match first_line {
+        Some(first_line) if first_line.chars().count() <= MAX_TITLE_LENGTH => first_line.to_owned(),
+        _ => name?.to_owned(),
+    };
+
+    // Retrieve the most relevant extract
+    let query_positive_terms = query.positive_terms();
+    let fragments = paragraphs(raw).iter().flat_map(|paragraph| sentence_fragments(paragraph)).collect::<Vec<_>>();
+    let extract = best_extract(fragments.iter().map(|fragment| fragment.as_str()), &query_positive_terms)?;
+
+    // Count words
+    let mut counter = WordCounter::new(&query_positive_terms, &lang);
+    counter.add_text(raw, TextStyle::default());
+    let (term_counts, word_count, common_words) = counter.finish();
+
+    Some(DocumentResult {
+        cid: String::new(),
+        paths: Vec::new(),
+        favicons: Vec::new(),
+        title: Some(title),
+        h1: None,
+        description: None,
+        extract: Some(extract),
+        preview_image: None,
+        site_name: None,
+        published: None,
+
+        media: Vec::new(),
+        structured_data: Vec::new(),
+
+        term_counts,
+        word_count,
+        common_words,
+    })
+}
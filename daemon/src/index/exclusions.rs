@@ -0,0 +1,91 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use super::*;
+
+/// Rules from the config that keep some pinned content out of the index
+pub struct Exclusions {
+    cids: HashSet<String>,
+    paths: GlobSet,
+    pin_names: GlobSet,
+    max_document_size: Option<u64>,
+}
+
+fn build_glob_set(patterns: &[String], option: &str) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .unwrap_or_else(|e| panic!("Invalid {option} pattern {pattern}: {e}"));
+        builder.add(glob);
+    }
+    builder.build().unwrap_or_else(|e| panic!("Invalid {option} patterns: {e}"))
+}
+
+impl Exclusions {
+    pub fn new(config: &Args) -> Exclusions {
+        Exclusions {
+            cids: config.exclude_cids.iter().map(|cid| normalize_cid(cid).unwrap_or_else(|| panic!("Invalid cid in exclude_cids: {cid}"))).collect(),
+            paths: build_glob_set(&config.exclude_paths, "exclude_paths"),
+            pin_names: build_glob_set(&config.exclude_pin_names, "exclude_pin_names"),
+            max_document_size: config.max_document_size.map(|size| size as u64),
+        }
+    }
+
+    /// Returns true if a normalized cid is denied, along with everything it contains
+    pub fn excludes_cid(&self, cid: &str) -> bool {
+        self.cids.contains(cid)
+    }
+
+    /// Returns true if a path relative to a pinned root, such as `/backups/2023.html`, is excluded
+    pub fn excludes_path(&self, path: &str) -> bool {
+        self.paths.is_match(path.trim_end_matches('/'))
+    }
+
+    pub fn excludes_pin_name(&self, name: &str) -> bool {
+        self.pin_names.is_match(name)
+    }
+
+    /// Returns true if a document is known to be larger than the size limit
+    pub fn excludes_size(&self, size: Option<u64>) -> bool {
+        self.max_document_size.zip(size).is_some_and(|(max_document_size, size)| size > max_document_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exclusions(paths: &[&str], pin_names: &[&str], max_document_size: Option<u64>) -> Exclusions {
+        let to_strings = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>();
+        Exclusions {
+            cids: HashSet::new(),
+            paths: build_glob_set(&to_strings(paths), "exclude_paths"),
+            pin_names: build_glob_set(&to_strings(pin_names), "exclude_pin_names"),
+            max_document_size,
+        }
+    }
+
+    #[test]
+    fn test_globs() {
+        let exclusions = exclusions(&["/private/**", "/*.pdf", "/drafts"], &["backup-*"], None);
+        assert!(exclusions.excludes_path("/private/notes.html"));
+        assert!(exclusions.excludes_path("/private/2023/notes.html"));
+        assert!(exclusions.excludes_path("/report.pdf"));
+        assert!(!exclusions.excludes_path("/docs/report.pdf"), "* shouldn't match slashes");
+        assert!(exclusions.excludes_path("/drafts/"), "Folder paths should match without their trailing slash");
+        assert!(!exclusions.excludes_path("/drafts.html"));
+        assert!(exclusions.excludes_pin_name("backup-2023"));
+        assert!(!exclusions.excludes_pin_name("website"));
+    }
+
+    #[test]
+    fn test_size() {
+        let limited = exclusions(&[], &[], Some(1000));
+        assert!(limited.excludes_size(Some(1001)));
+        assert!(!limited.excludes_size(Some(1000)));
+        assert!(!limited.excludes_size(None));
+
+        let unlimited = exclusions(&[], &[], None);
+        assert!(!unlimited.excludes_size(Some(u64::MAX)));
+    }
+}
@@ -3,18 +3,26 @@ use super::*;
 #[derive(Clone)]
 pub struct DocumentIndex {
     config: Arc<Args>,
+    source: Arc<dyn ContentSource>,
+    exclusions: Arc<Exclusions>,
     inner: Arc<RwLock<DocumentIndexInner>>,
 }
 
 #[allow(dead_code)]
 impl DocumentIndex {
-    pub async fn new(config: Arc<Args>) -> DocumentIndex {
+    pub async fn new(config: Arc<Args>, source: Arc<dyn ContentSource>) -> DocumentIndex {
         DocumentIndex {
-            inner: Arc::new(RwLock::new(DocumentIndexInner::new(Arc::clone(&config)).await)),
+            inner: Arc::new(RwLock::new(DocumentIndexInner::new(Arc::clone(&config), Arc::clone(&source)).await)),
+            exclusions: Arc::new(Exclusions::new(&config)),
+            source,
             config,
         }
     }
 
+    pub fn source(&self) -> Arc<dyn ContentSource> {
+        Arc::clone(&self.source)
+    }
+
     pub async fn run(&self) {
         let f1 = self.refresh();
         let f2 = self.sweep();
@@ -35,22 +43,17 @@ impl DocumentIndex {
         let mut listed = HashSet::new();
         let mut loaded = self.documents().await;
 
-        fn normalize_cid(cid: impl AsRef<str>) -> Option<String> {
-            let cid = Cid::try_from(cid.as_ref()).ok()?;
-            let cid = cid.into_v1().ok()?;
-            Some(cid.to_string())
-        }
-
         let mut last_printed_error = None;
-        let ipfs_rpc = &self.config.ipfs_rpc;
         let mut previous_load = -1.0;
+        let mut previous_pinned = HashSet::new();
+        let mut prune_needed = true;
         loop {
             let mut to_list = Vec::new();
             let mut to_load = HashMap::new();
             let mut to_load_unprioritized = HashSet::new();
 
             // List pinned elements
-            let pinned = match list_pinned(&self.config.ipfs_rpc).await {
+            let pinned = match self.source.list_pinned().await {
                 Ok(pinned) => pinned,
                 Err(e) => {
                     let e_string = e.to_string();
@@ -63,30 +66,104 @@ impl DocumentIndex {
                 }
             };
             last_printed_error = None;
-            to_list.extend(pinned.iter().filter_map(normalize_cid).filter(|cid| !listed.contains(cid)));
+
+            // Leave out excluded pins, so that pruning drops what was indexed from them
+            let excluded_pins = match self.config.exclude_pin_names.is_empty() {
+                true => HashSet::new(),
+                false => match self.source.pin_names().await {
+                    Ok(names) => names.into_iter().filter(|(_, name)| self.exclusions.excludes_pin_name(name)).filter_map(|(cid, _)| normalize_cid(cid)).collect(),
+                    Err(e) => {
+                        warn!("Error while listing pin names: {e}");
+                        sleep(Duration::from_secs(REFRESH_INTERVAL)).await;
+                        continue;
+                    }
+                },
+            };
+            let pinned = pinned
+                .iter()
+                .filter_map(normalize_cid)
+                .filter(|cid| !self.exclusions.excludes_cid(cid) && !excluded_pins.contains(cid))
+                .collect::<HashSet<_>>();
+            if pinned != previous_pinned {
+                prune_needed = true;
+                previous_pinned = pinned.clone();
+            }
+            for cid in pinned.iter().filter(|cid| !listed.contains(*cid)) {
+                let rules = Arc::new(fetch_robots_rules(self.source.as_ref(), cid, "/").await);
+                to_list.push((cid.clone(), String::from("/"), rules));
+            }
 
             // Explore directories
             let start = Instant::now();
             let mut i = 0;
+            let mut listing_failed = false;
             if !to_list.is_empty() {debug!("{} elements to list", to_list.len())}
-            while let Some(cid) = to_list.pop() {
+            while let Some((cid, path, rules)) = to_list.pop() {
                 if !listed.insert(cid.clone()) {continue}
-                let new_links = match ls(ipfs_rpc, cid.clone()).await {
-                    Ok(new_links) => new_links,
-                    Err(e) => {
-                        warn!("Error listing potential directory: {e:?}");
-                        continue;
+                let (mut entries, already_listed): (EntryStream, bool) = match self.listed_children(&cid, 0).await {
+                    Some(children) => (self.stream_listed_children(cid.clone(), children), true),
+                    None => match self.source.ls(cid.clone()).await {
+                        Ok(entries) => (entries, false),
+                        Err(e) => {
+                            warn!("Error listing potential directory: {e:?}");
+                            listed.remove(&cid);
+                            listing_failed = true;
+                            continue;
+                        },
                     },
                 };
-                for (child_cid, child_name, child_is_folder) in new_links {
+                let mut children = Vec::new();
+                let mut children_stored = 0;
+                let mut complete = true;
+                while let Some(entry) = entries.next().await {
+                    let (child_cid, child_name, child_is_folder, child_size) = match entry {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            warn!("Error while listing directory {cid}: {e}");
+                            complete = false;
+                            break;
+                        },
+                    };
+                    if !already_listed {
+                        children.push((child_cid.clone(), child_name.clone(), child_is_folder, child_size));
+                        if children.len() >= LISTING_FLUSH_THRESHOLD {
+                            self.set_listed(&cid, children_stored, &children, false).await;
+                            children_stored += children.len();
+                            children.clear();
+                        }
+                    }
                     let child_cid = normalize_cid(child_cid).unwrap();
+
+                    // Excluded entries are unlinked from the ancestor graph, so that they get pruned if they were indexed before
+                    let child_path = match child_is_folder {
+                        true => format!("{path}{child_name}/"),
+                        false => format!("{path}{child_name}"),
+                    };
+                    let excluded = self.exclusions.excludes_cid(&child_cid)
+                        || self.exclusions.excludes_path(&child_path)
+                        || (!child_is_folder && self.exclusions.excludes_size(child_size))
+                        || !rules.is_allowed(&child_path)
+                        || rules.is_robots_file(&child_path);
+                    if excluded {
+                        if self.remove_ancestor(&child_cid, &cid).await {
+                            prune_needed = true;
+                        }
+                        continue;
+                    }
+
                     if child_is_folder {
+                        // DNS pins are websites of their own, with their own robots.txt
+                        let is_dns_pin = path == "/" && parse_dns_pin_name(&child_name).is_some();
                         self.add_ancestor(&child_cid, child_name, &cid).await;
                         if !listed.contains(&child_cid) {
-                            to_list.push(child_cid);
+                            let rules = match is_dns_pin {
+                                true => Arc::new(fetch_robots_rules(self.source.as_ref(), &child_cid, &child_path).await),
+                                false => Arc::clone(&rules),
+                            };
+                            to_list.push((child_cid, child_path, rules));
                         }
                     } else if !loaded.contains(&child_cid) {
-                        if child_name.ends_with(".html") {
+                        if DocumentKind::is_prioritized(&child_name) {
                             to_load.insert(child_cid, (child_name, cid.clone()));
                         } else if self.config.crawl_unprioritized {
                             to_load_unprioritized.insert((child_cid, child_name, cid.clone()));
@@ -94,44 +171,54 @@ impl DocumentIndex {
                     } else {
                         self.add_ancestor(&child_cid, child_name, &cid).await;
                     }
+
+                    // Huge directories shouldn't delay loading documents until they are fully listed
+                    if to_load.len() >= LISTING_FLUSH_THRESHOLD {
+                        let batch = to_load.drain().filter(|(cid, _)| loaded.insert(cid.clone())).map(|(cid, (name, parent_cid))| (cid, name, parent_cid)).collect::<Vec<_>>();
+                        debug!("Loading {} documents while listing ({:.02?}s)", batch.len(), start.elapsed().as_secs_f32());
+                        self.load_documents(batch, start).await;
+                    }
                 }
-                to_list.sort();
-                to_list.dedup();
+                if !complete {
+                    // Documents found so far are still loaded, but the directory will be listed again
+                    listed.remove(&cid);
+                    listing_failed = true;
+                    continue;
+                }
+                if !already_listed {
+                    self.set_listed(&cid, children_stored, &children, true).await;
+                }
+                to_list.sort_by(|(cid1, _, _), (cid2, _, _)| cid1.cmp(cid2));
+                to_list.dedup_by(|(cid1, _, _), (cid2, _, _)| cid1 == cid2);
                 i += 1;
                 if i % 500 == 0 {
                     debug!("Still listing pinned files ({i} in {:.02})", start.elapsed().as_secs_f32());
                 }
             }
 
-            // Load documents
-            i = 0;
-            if !to_load.is_empty() {debug!("{} documents to load ({:.02?}s)", to_load.len(), start.elapsed().as_secs_f32())}
-            for (cid, (name, parent_cid)) in to_load.drain() {
-                if !loaded.insert(cid.clone()) {continue}
-                let Ok(document) = fetch_document(ipfs_rpc, &cid).await else {continue};
-                let Some(inspected) = inspect_document(document) else {continue};
-                self.add_document(&cid, inspected).await;
-                self.add_ancestor(&cid, name, &parent_cid).await;
-                i += 1;
-                if i % 500 == 0 {
-                    debug!("Still loading files ({i} in {:.02})", start.elapsed().as_secs_f32());
+            // Remove what is no longer pinned
+            // Pruning is delayed if listing failed as the ancestor graph might then be incomplete
+            if prune_needed && !listing_failed {
+                let removed = self.prune(&pinned).await;
+                if !removed.is_empty() {
+                    info!("Removed {} documents and folders that are no longer pinned", removed.len());
+                }
+                for cid in removed {
+                    listed.remove(&cid);
+                    loaded.remove(&cid);
                 }
+                prune_needed = false;
             }
 
+            // Load documents
+            let to_load = to_load.drain().filter(|(cid, _)| loaded.insert(cid.clone())).map(|(cid, (name, parent_cid))| (cid, name, parent_cid)).collect::<Vec<_>>();
+            if !to_load.is_empty() {debug!("{} documents to load ({:.02?}s)", to_load.len(), start.elapsed().as_secs_f32())}
+            self.load_documents(to_load, start).await;
+
             // Load unprioritized documents
-            i = 0;
+            let to_load_unprioritized = to_load_unprioritized.drain().filter(|(cid, _, _)| loaded.insert(cid.clone())).collect::<Vec<_>>();
             if !to_load_unprioritized.is_empty() {debug!("{} unprioritized documents to load ({:.02?}s)", to_load_unprioritized.len(), start.elapsed().as_secs_f32())};
-            for (cid, name, parent_cid) in to_load_unprioritized.drain() {
-                if !loaded.insert(cid.clone()) {continue}
-                let Ok(document) = fetch_document(ipfs_rpc, &cid).await else {continue};
-                let Some(inspected) = inspect_document(document) else {continue};
-                self.add_document(&cid, inspected).await;
-                self.add_ancestor(&cid, name, &parent_cid).await;
-                i += 1;
-                if i % 500 == 0 {
-                    debug!("Still loading files ({i} in {:.02})", start.elapsed().as_secs_f32());
-                }
-            }
+            self.load_documents(to_load_unprioritized, start).await;
             
             self.update_filter().await;
             let load = self.get_filter().await.load()*100.0;
@@ -143,6 +230,55 @@ impl DocumentIndex {
         }
     }
 
+    /// Fetches and inspects documents concurrently, then adds them to the index by batches.
+    /// Parsing happens on the blocking thread pool, and no lock is held while documents are being fetched or parsed.
+    async fn load_documents(&self, to_load: Vec<(String, String, String)>, start: Instant) {
+        let source = &self.source;
+        let config = &self.config;
+        let mut batches = futures::stream::iter(to_load)
+            .map(|(cid, name, parent_cid)| async move {
+                let document = source.fetch_document(&cid).await.ok()?;
+                // Listings don't always tell the size of documents
+                if config.max_document_size.is_some_and(|max_document_size| document.len() > max_document_size) {
+                    return None;
+                }
+                let config = Arc::clone(config);
+                let name2 = name.clone();
+                let mut inspected = match tokio::task::spawn_blocking(move || inspect_document(document, &name2, &config)).await {
+                    Ok(inspected) => inspected?,
+                    Err(e) => {
+                        warn!("Failed to inspect document {cid}: {e}");
+                        return None;
+                    }
+                };
+                if inspected.date.is_none() && config.mtime_dates {
+                    // Documents that don't say when they were published are dated by their UnixFS metadata
+                    if let Some(date) = source.mtime(&cid).await.ok().flatten().and_then(date_from_timestamp) {
+                        inspected.set_date(date);
+                    }
+                }
+                Some((cid, name, parent_cid, inspected))
+            })
+            .buffer_unordered(self.config.load_parallelism.max(1))
+            .filter_map(futures::future::ready)
+            .ready_chunks(LOAD_BATCH_SIZE);
+
+        let mut i = 0;
+        while let Some(batch) = batches.next().await {
+            let previous_i = i;
+            i += batch.len();
+            let mut inner = self.inner.write().await;
+            for (cid, name, parent_cid, inspected) in batch {
+                inner.add_document(&cid, inspected);
+                inner.add_ancestor(&cid, name, &parent_cid);
+            }
+            drop(inner);
+            if i / 500 != previous_i / 500 {
+                debug!("Still loading files ({i} in {:.02})", start.elapsed().as_secs_f32());
+            }
+        }
+    }
+
     pub async fn folders(&self) -> HashMap<String, usize> {
         self.inner.read().await.folders()
     }
@@ -163,6 +299,10 @@ impl DocumentIndex {
         self.inner.write().await.add_ancestor(cid, name, folder_cid);
     }
 
+    pub async fn remove_ancestor(&self, cid: &String, folder_cid: &String) -> bool {
+        self.inner.write().await.remove_ancestor(cid, folder_cid)
+    }
+
     pub async fn add_ancestors(&self, ancestors: Vec<(&String, String, &String)>) {
         let mut inner = self.inner.write().await;
         for (cid, name, folder_cid) in ancestors {
@@ -170,10 +310,57 @@ impl DocumentIndex {
         }
     }
 
+    /// Returns a chunk of the children of a directory if it was listed before.
+    /// Directories are immutable so a listing never gets outdated.
+    pub async fn listed_children(&self, cid: &str, offset: usize) -> Option<Vec<ListedEntry>> {
+        self.inner.read().await.listed_children(cid, offset, LISTING_FLUSH_THRESHOLD).await
+    }
+
+    /// Streams the children of a listed directory, reading them chunk by chunk after the `first` one
+    fn stream_listed_children(&self, cid: String, first: Vec<ListedEntry>) -> EntryStream {
+        let index = self.clone();
+        let stream = futures::stream::unfold((first, 0, false), move |(chunk, offset, done)| {
+            let index = index.clone();
+            let cid = cid.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                let next_offset = offset + chunk.len();
+                if chunk.len() < LISTING_FLUSH_THRESHOLD {
+                    return Some((futures::stream::iter(chunk.into_iter().map(Ok)).boxed(), (Vec::new(), next_offset, true)));
+                }
+                match index.listed_children(&cid, next_offset).await {
+                    Some(next) => Some((futures::stream::iter(chunk.into_iter().map(Ok)).boxed(), (next, next_offset, false))),
+                    None => {
+                        let entries = chunk.into_iter().map(Ok).chain(std::iter::once(Err(SourceError::NotFound(format!("listing of {cid} at {next_offset}")))));
+                        Some((futures::stream::iter(entries).boxed(), (Vec::new(), next_offset, true)))
+                    },
+                }
+            }
+        });
+        Box::pin(stream.flatten())
+    }
+
+    pub async fn set_listed(&self, cid: &str, offset: usize, children: &[ListedEntry], complete: bool) {
+        self.inner.read().await.set_listed(cid, offset, children, complete).await
+    }
+
     pub async fn build_path(&self, cid: &String) -> Option<Vec<Vec<String>>> {
         self.inner.read().await.build_path(cid)
     }
 
+    /// Removes documents and folders that can no longer be reached from pinned elements.
+    /// Returns the cids that were removed.
+    pub async fn prune(&self, pinned: &HashSet<String>) -> Vec<String> {
+        let mut inner = self.inner.write().await;
+        let unreachable = inner.unreachable(pinned);
+        if unreachable.is_empty() {
+            return Vec::new();
+        }
+        inner.remove_cids(unreachable).await
+    }
+
     pub async fn update_filter(&self) {
         self.inner.write().await.update_filter().await;
     }
@@ -1,5 +1,22 @@
 use super::*;
 
+/// Reads the domain and the path of a DNS pin from the name of its link, such as `dns-pin-example.com/docs-0`
+pub(super) fn parse_dns_pin_name(name: &str) -> Option<(&str, &str)> {
+    let dns_pin_with_suffix = name.strip_prefix("dns-pin-")?;
+    let i = dns_pin_with_suffix.bytes().rposition(|c| c == b'-')?;
+    let dns_pin = dns_pin_with_suffix.split_at(i).0;
+    Some(dns_pin.split_once('/').unwrap_or((dns_pin, "/")))
+}
+
+/// Lists the filter words under which a domain can be found with the `site:` operator.
+/// Parent domains are included so that `site:example.com` also reaches `blog.example.com`.
+fn site_words(domain: &str) -> Vec<String> {
+    let domain = domain.to_lowercase();
+    let mut words = vec![format!("site={domain}")];
+    words.extend(domain.match_indices('.').map(|(i, _)| format!("site={}", &domain[i + 1..])));
+    words
+}
+
 impl DocumentIndexInner {
     pub fn add_ancestor(&mut self, cid: &String, name: String, folder_cid: &String) {
         let lcid = match self.cids.get_by_right(cid) {
@@ -9,6 +26,7 @@ impl DocumentIndexInner {
                 self.cid_counter += 1;
                 self.cids.insert(lcid, cid.clone());
                 self.folders.insert(lcid);
+                self.ancestry_changed(lcid);
                 lcid
             }
         };
@@ -19,12 +37,114 @@ impl DocumentIndexInner {
                 let lcid = LocalCid(self.cid_counter);
                 self.cid_counter += 1;
                 self.cids.insert(lcid, folder_cid.clone());
+                self.ancestry_changed(lcid);
                 lcid
             }
         };
-        self.folders.insert(ancestor_lcid);
+        if self.folders.insert(ancestor_lcid) {
+            self.ancestry_changed(ancestor_lcid);
+        }
 
-        self.ancestors.entry(lcid).or_default().insert(ancestor_lcid, name);
+        if let Some((domain, _)) = parse_dns_pin_name(&name) {
+            site_words(domain).iter().for_each(|word| self.filter.add_word::<DocumentIndex>(word));
+        }
+
+        let previous_name = self.ancestors.entry(lcid).or_default().insert(ancestor_lcid, name.clone());
+        if previous_name.as_ref() != Some(&name) {
+            self.ancestry_changed(lcid);
+            for path in self.build_path(cid).unwrap_or_default().into_iter().filter(|path| path.len() > 1) {
+                self.filter.add_word::<DocumentIndex>(&format!("path=/{}", path[1..].join("/")));
+            }
+        }
+    }
+
+    /// Removes the link between a cid and one of its folders.
+    /// Returns true if there was such a link.
+    pub fn remove_ancestor(&mut self, cid: &String, folder_cid: &String) -> bool {
+        let (Some(lcid), Some(ancestor_lcid)) = (self.cids.get_by_right(cid).copied(), self.cids.get_by_right(folder_cid).copied()) else { return false };
+        let Some(ancestors) = self.ancestors.get_mut(&lcid) else { return false };
+        if ancestors.remove(&ancestor_lcid).is_none() {
+            return false;
+        }
+        if ancestors.is_empty() {
+            self.ancestors.remove(&lcid);
+        }
+        self.ancestry_changed(lcid);
+        true
+    }
+
+    /// Lists the filter words of scope operators: the domains that DNS pins make content available under for `site:`,
+    /// and the paths of folders for `path:`.
+    pub(super) fn scope_words(&self) -> HashSet<String> {
+        let mut words = self.ancestors
+            .values()
+            .flat_map(|ancestors| ancestors.values())
+            .filter_map(|name| parse_dns_pin_name(name))
+            .flat_map(|(domain, _)| site_words(domain))
+            .collect::<HashSet<_>>();
+        for cid in self.folders.iter().filter_map(|lcid| self.cids.get_by_left(lcid)) {
+            let paths = self.build_path(cid).unwrap_or_default();
+            words.extend(paths.into_iter().filter(|path| path.len() > 1).map(|path| format!("path=/{}", path[1..].join("/"))));
+        }
+        // Paths are also built from the folder directly containing documents, so `path:/name` can target a document
+        words.extend(self.ancestors
+            .values()
+            .flat_map(|ancestors| ancestors.values())
+            .filter(|name| parse_dns_pin_name(name).is_none())
+            .map(|name| format!("path=/{name}")));
+        words
+    }
+
+    /// Lists the documents a scope operator matches, for queries that have nothing else to find candidates with
+    pub(super) fn scope_candidates(&self, name: &str, value: &str) -> Vec<LocalCid> {
+        self.cids
+            .iter()
+            .filter(|(lcid, _)| !self.folders.contains(*lcid))
+            .filter(|(_, cid)| self.build_path(cid).unwrap_or_default().iter().any(|path| scope_matches(name, value, path)))
+            .map(|(lcid, _)| *lcid)
+            .collect()
+    }
+
+    /// Lists cids that can't be reached from any of the roots by going down the ancestor graph.
+    pub fn unreachable(&self, roots: &HashSet<String>) -> HashSet<LocalCid> {
+        let mut children: HashMap<LocalCid, Vec<LocalCid>> = HashMap::new();
+        for (lcid, ancestors) in &self.ancestors {
+            for ancestor_lcid in ancestors.keys() {
+                children.entry(*ancestor_lcid).or_default().push(*lcid);
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut to_visit = roots.iter().filter_map(|cid| self.cids.get_by_right(cid)).copied().collect::<Vec<_>>();
+        while let Some(lcid) = to_visit.pop() {
+            if !reachable.insert(lcid) {
+                continue;
+            }
+            if let Some(children) = children.get(&lcid) {
+                to_visit.extend(children.iter().filter(|child| !reachable.contains(*child)));
+            }
+        }
+
+        self.cids.left_values().filter(|lcid| !reachable.contains(*lcid)).copied().collect()
+    }
+
+    /// Removes cids from the ancestor graph, the folder set and the cid map.
+    /// Returns the cids that were removed.
+    pub(super) fn forget_cids(&mut self, lcids: &HashSet<LocalCid>) -> Vec<String> {
+        self.ancestors.retain(|lcid, _| !lcids.contains(lcid));
+        let mut changed = Vec::new();
+        for (lcid, ancestors) in self.ancestors.iter_mut() {
+            let previous_len = ancestors.len();
+            ancestors.retain(|ancestor_lcid, _| !lcids.contains(ancestor_lcid));
+            if ancestors.len() != previous_len {
+                changed.push(*lcid);
+            }
+        }
+        changed.into_iter().for_each(|lcid| self.ancestry_changed(lcid));
+        self.ancestors.retain(|_, ancestors| !ancestors.is_empty());
+        self.folders.retain(|lcid| !lcids.contains(lcid));
+
+        lcids.iter().filter_map(|lcid| self.cids.remove_by_left(lcid)).map(|(_, cid)| cid).collect()
     }
 
     pub fn build_path(&self, cid: &String) -> Option<Vec<Vec<String>>> {
@@ -62,23 +182,16 @@ impl DocumentIndexInner {
         // Resolve the root cid to build final paths
         let mut final_paths = Vec::new();
         for (root, mut path) in paths {
-            if let Some(first) = path.first() {
-                if first.starts_with("dns-pin-") {
-                    let dns_pin_with_suffix = first.split_at(8).1;
-                    if let Some(i) = dns_pin_with_suffix.bytes().rposition(|c| c == b'-') {
-                        let dns_pin = dns_pin_with_suffix.split_at(i).0;
-                        let (domain, path_start) = dns_pin.split_once('/').unwrap_or((dns_pin, "/"));
-                        let (domain, path_start) = (domain.to_owned(), path_start.to_owned());
-                        path[0] = domain;
-                        for path_part in path_start.split('/').rev() {
-                            if !path_part.is_empty() {
-                                path.insert(1, path_part.to_owned());
-                            }
-                        }
-                        final_paths.push(path);
-                        continue;
+            if let Some((domain, path_start)) = path.first().and_then(|first| parse_dns_pin_name(first)) {
+                let (domain, path_start) = (domain.to_owned(), path_start.to_owned());
+                path[0] = domain;
+                for path_part in path_start.split('/').rev() {
+                    if !path_part.is_empty() {
+                        path.insert(1, path_part.to_owned());
                     }
                 }
+                final_paths.push(path);
+                continue;
             }
             let root_cid = match self.cids.get_by_left(&root) {
                 Some(root_cid) => root_cid.to_owned(),
@@ -1,7 +1,7 @@
 use super::*;
 
 pub(super) struct DocumentIndexInner {
-    config: Arc<Args>,
+    source: Arc<dyn ContentSource>,
 
     pub(super) filter: Filter<FILTER_SIZE>,
     filter_needs_update: bool,
@@ -11,38 +11,49 @@ pub(super) struct DocumentIndexInner {
     pub(super) folders: HashSet<LocalCid>,
     pub(super) cids: BiHashMap<LocalCid, String>,
     cids_to_store: Vec<LocalCid>,
+    keys_to_store: Vec<(LocalCid, DocumentKeys)>,
+    ancestry_to_store: HashSet<LocalCid>,
 
     loaded_index: HashSet<String>,
     changed_index: HashSet<String>,
     in_use_index: HashMap<String, usize>,
     in_memory_index: HashMap<String, HashMap<LocalCid, f32>>,
-    // todo filters
+
+    loaded_filters: HashSet<(String, String)>,
+    changed_filters: HashSet<(String, String)>,
+    in_memory_filters: HashMap<(String, String), Vec<LocalCid>>,
 
     index_db: DbIndexController,
 }
 
 impl DocumentIndexInner {
-    pub async fn new(config: Arc<Args>) -> DocumentIndexInner {
-        let (db, cid_counter, cids) = open_database(Arc::clone(&config));
+    pub async fn new(config: Arc<Args>, source: Arc<dyn ContentSource>) -> DocumentIndexInner {
+        let (db, cid_counter, cids, ancestors, folders) = open_database(config);
         let index_db = DbIndexController::from(db);
 
         let mut index = DocumentIndexInner {
-            config,
+            source,
 
             filter: Filter::new(),
             filter_needs_update: !cids.is_empty(),
             
             cid_counter,
-            ancestors: HashMap::new(),
-            folders: HashSet::new(),
+            ancestors,
+            folders,
             cids,
             cids_to_store: Vec::new(),
+            keys_to_store: Vec::new(),
+            ancestry_to_store: HashSet::new(),
 
             loaded_index: HashSet::new(),
             changed_index: HashSet::new(),
             in_use_index: HashMap::new(),
             in_memory_index: HashMap::new(),
 
+            loaded_filters: HashSet::new(),
+            changed_filters: HashSet::new(),
+            in_memory_filters: HashMap::new(),
+
             index_db,
         };
         index.update_filter().await;
@@ -91,6 +102,36 @@ impl DocumentIndexInner {
         }
     }
 
+    async fn load_filter_batch(&mut self, keys: Vec<(String, String)>) {
+        let keys = keys.into_iter().filter(|key| !self.loaded_filters.contains(key)).collect::<Vec<_>>();
+        if keys.is_empty() {
+            return;
+        }
+        let new_data = self.index_db.get_filters(keys).await.unwrap_or_default();
+        for (key, lcids) in new_data {
+            self.loaded_filters.insert(key.clone());
+            self.in_memory_filters.entry(key).or_default().extend(lcids.into_iter().filter(|lcid| self.cids.contains_left(lcid)));
+        }
+    }
+    async fn unload_filters(&mut self) {
+        // Load entries that changed so that they are written back complete
+        let to_load = self.changed_filters.iter().filter(|key| !self.loaded_filters.contains(*key)).cloned().collect::<Vec<_>>();
+        self.load_filter_batch(to_load).await;
+
+        let mut in_memory_filters = std::mem::take(&mut self.in_memory_filters);
+        let items = self.changed_filters.drain().map(|key| {
+            let lcids = in_memory_filters.remove(&key).unwrap_or_default();
+            (key, lcids)
+        }).collect::<Vec<_>>();
+        self.loaded_filters.clear();
+        if items.is_empty() {
+            return;
+        }
+        if let Err(e) = self.index_db.put_filters(items).await {
+            error!("Failed to unload filters: {e:?}");
+        }
+    }
+
     // TODO: optimize
     pub(super) async fn sweep(&mut self) {
         let start = Instant::now();
@@ -100,8 +141,11 @@ impl DocumentIndexInner {
         if count > 0 {
             trace!("Sweeped {count} words from index in {}ms", start.elapsed().as_millis());
         }
+        self.unload_filters().await;
 
-        let cids = std::mem::take(&mut self.cids_to_store).into_iter().filter_map(|lcid| self.cids.get_by_left(&lcid).map(|cid| (lcid, cid.to_owned()))).collect::<Vec<_>>();
+        let ancestry_to_store = std::mem::take(&mut self.ancestry_to_store);
+        let lcids_to_store = std::mem::take(&mut self.cids_to_store).into_iter().chain(ancestry_to_store.iter().copied()).collect::<HashSet<_>>();
+        let cids = lcids_to_store.into_iter().filter_map(|lcid| self.cids.get_by_left(&lcid).map(|cid| (lcid, cid.to_owned()))).collect::<Vec<_>>();
         let count = cids.len();
         if let Err(e) = self.index_db.put_cids(cids).await {
             error!("Failed to store cids: {e:?}")
@@ -109,6 +153,50 @@ impl DocumentIndexInner {
         if count > 0 {
             trace!("Stored {count} cids in database");
         }
+
+        let keys = std::mem::take(&mut self.keys_to_store);
+        if !keys.is_empty() {
+            if let Err(e) = self.index_db.put_document_keys(keys).await {
+                error!("Failed to store document keys: {e:?}")
+            }
+        }
+
+        if ancestry_to_store.is_empty() {
+            return;
+        }
+        let ancestors = ancestry_to_store.iter().map(|lcid| (*lcid, self.ancestors.get(lcid).cloned().unwrap_or_default())).collect::<Vec<_>>();
+        let folders = ancestry_to_store.iter().map(|lcid| (*lcid, self.folders.contains(lcid))).collect::<Vec<_>>();
+        if let Err(e) = self.index_db.put_ancestors(ancestors).await {
+            error!("Failed to store ancestors: {e:?}")
+        }
+        if let Err(e) = self.index_db.put_folders(folders).await {
+            error!("Failed to store folders: {e:?}")
+        }
+        trace!("Stored ancestry of {} cids in database", ancestry_to_store.len());
+    }
+
+    /// Marks the ancestors and folder status of a cid for storage at the next sweep
+    pub(super) fn ancestry_changed(&mut self, lcid: LocalCid) {
+        self.ancestry_to_store.insert(lcid);
+    }
+
+    /// Returns up to `limit` children of a directory that was fully listed before, starting at `offset`
+    pub(super) async fn listed_children(&self, cid: &str, offset: usize, limit: usize) -> Option<Vec<ListedEntry>> {
+        match self.index_db.get_listed(cid.to_owned(), offset as u32, limit as u32).await {
+            Ok(children) => children,
+            Err(e) => {
+                error!("Failed to get listed directory {cid}: {e:?}");
+                None
+            }
+        }
+    }
+
+    /// Stores a chunk of the children of a directory, starting at `offset`.
+    /// The directory counts as listed once its last chunk is stored with `complete` set.
+    pub(super) async fn set_listed(&self, cid: &str, offset: usize, children: &[ListedEntry], complete: bool) {
+        if let Err(e) = self.index_db.put_listed(cid.to_owned(), offset as u32, children.to_vec(), complete).await {
+            error!("Failed to store listed directory {cid}: {e:?}");
+        }
     }
 
     pub fn folders(&self) -> HashMap<String, usize> {
@@ -147,9 +235,16 @@ impl DocumentIndexInner {
         self.cids_to_store.push(lcid);
         self.folders.remove(&lcid);
 
-        // Index by words
+        // Remember the keys of the document so that it can be removed without scanning the database
+        let mut words = doc.words.iter().chain(&doc.stems).cloned().collect::<Vec<_>>();
+        words.sort();
+        words.dedup();
+        let filter_keys = doc.filters.iter().map(|(key, value)| format!("{key}={value}")).collect();
+        self.keys_to_store.push((lcid, (words, filter_keys)));
+
+        // Index by words and stems
         let word_count = doc.words.len() as f64;
-        for word in doc.words {
+        for word in doc.words.into_iter().chain(doc.stems) {
             let frequencies = self.in_memory_index.entry(word.clone()).or_default();
             *frequencies.entry(lcid).or_insert(0.) += 1. / word_count as f32;
             self.changed_index.insert(word.clone());
@@ -157,10 +252,12 @@ impl DocumentIndexInner {
         }
         
         // Index by filters
-        /*for (key, value) in doc.filters {
-            self.filters.entry((key.to_string(), value.clone())).or_default().push(lcid);
-            self.filter.add_word::<DocumentIndex>(&format!("{key}={value}"));
-        }*/
+        for (key, value) in doc.filters {
+            let key = (key.to_string(), value);
+            self.filter.add_word::<DocumentIndex>(&format!("{}={}", key.0, key.1));
+            self.in_memory_filters.entry(key.clone()).or_default().push(lcid);
+            self.changed_filters.insert(key);
+        }
     }
 
     pub async fn update_filter(&mut self) {
@@ -168,30 +265,72 @@ impl DocumentIndexInner {
             return;
         }
         let start = Instant::now();
-        self.filter = match self.index_db.compute_filter().await {
+        let mut filter = match self.index_db.compute_filter().await {
             Ok(filter) => filter,
             Err(e) => {
                 error!("Failed to compute filter: {e:?}");
                 return;
             },
         };
+        // Words that haven't been written to the database yet
+        for (word, frequencies) in &self.in_memory_index {
+            if !frequencies.is_empty() {
+                filter.add_word::<DocumentIndex>(word);
+            }
+        }
+        for ((name, value), lcids) in &self.in_memory_filters {
+            if !lcids.is_empty() {
+                filter.add_word::<DocumentIndex>(&format!("{name}={value}"));
+            }
+        }
+        for word in self.scope_words() {
+            filter.add_word::<DocumentIndex>(&word);
+        }
+        self.filter = filter;
         self.filter_needs_update = false;
         trace!("Filter recomputed in {}ms", start.elapsed().as_millis());
     }
 
+    pub async fn remove_cids(&mut self, lcids: HashSet<LocalCid>) -> Vec<String> {
+        for (word, frequencies) in self.in_memory_index.iter_mut() {
+            let previous_len = frequencies.len();
+            frequencies.retain(|lcid, _| !lcids.contains(lcid));
+            if frequencies.len() != previous_len {
+                self.changed_index.insert(word.to_owned());
+            }
+        }
+        for (key, docs) in self.in_memory_filters.iter_mut() {
+            let previous_len = docs.len();
+            docs.retain(|lcid| !lcids.contains(lcid));
+            if docs.len() != previous_len {
+                self.changed_filters.insert(key.to_owned());
+            }
+        }
+        self.cids_to_store.retain(|lcid| !lcids.contains(lcid));
+        self.keys_to_store.retain(|(lcid, _)| !lcids.contains(lcid));
+        self.ancestry_to_store.retain(|lcid| !lcids.contains(lcid));
+        if let Err(e) = self.index_db.remove_cids(lcids.clone()).await {
+            error!("Failed to remove cids from database: {e:?}");
+        }
+        let removed = self.forget_cids(&lcids);
+
+        self.filter_needs_update = true;
+        self.update_filter().await;
+        removed
+    }
+
     pub async fn search(&mut self, query: Arc<Query>) -> ResultStream<DocumentResult> {
-        let mut terms = query.terms();
-        terms.sort();
-        terms.dedup();
-        terms.iter().for_each(|t| *self.in_use_index.entry((*t).to_owned()).or_default() += 1);
-        self.load_index_batch(terms.iter().map(|t| (*t).to_owned()).collect()).await;
-        
+        let keys = query.index_keys();
+        keys.iter().for_each(|k| *self.in_use_index.entry(k.to_owned()).or_default() += 1);
+        self.load_index_batch(keys.clone()).await;
+        self.load_filter_batch(query.filters().into_iter().map(|(name, value)| (name.to_owned(), value.to_owned())).collect()).await;
+
         let matching_docs = match query.match_score(&self.filter) > 0 {
-            true => query.matching_docs(&self.in_memory_index, &HashMap::new()), // TODO
+            true => query.matching_docs(&self.in_memory_index, &self.in_memory_filters, |lcid| self.cids.get_by_left(&lcid).and_then(|cid| self.build_path(cid)).unwrap_or_default(), |name, value| self.scope_candidates(name, value)),
             false => Vec::new(),
         };
 
-        terms.iter().for_each(|t| *self.in_use_index.entry((*t).to_owned()).or_default() -= 1);
+        keys.iter().for_each(|k| *self.in_use_index.entry(k.to_owned()).or_default() -= 1);
 
         let futures = matching_docs
             .into_iter()
@@ -203,7 +342,7 @@ impl DocumentIndexInner {
                 cid
             })
             .map(|cid| (cid, self.build_path(cid).unwrap_or_default()))
-            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
+            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.source)))
             .collect();
 
         Box::pin(DocumentResultStream { futures })
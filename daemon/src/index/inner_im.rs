@@ -1,7 +1,7 @@
 use super::*;
 
 pub(super) struct DocumentIndexInner {
-    config: Arc<Args>,
+    source: Arc<dyn ContentSource>,
 
     pub(super) filter: Filter<FILTER_SIZE>,
     filter_needs_update: bool,
@@ -16,9 +16,9 @@ pub(super) struct DocumentIndexInner {
 }
 
 impl DocumentIndexInner {
-    pub async fn new(config: Arc<Args>) -> DocumentIndexInner {
+    pub async fn new(_config: Arc<Args>, source: Arc<dyn ContentSource>) -> DocumentIndexInner {
         DocumentIndexInner {
-            config,
+            source,
             filter: Filter::new(),
             filter_needs_update: false,
 
@@ -35,6 +35,13 @@ impl DocumentIndexInner {
     
     pub(super) async fn sweep(&mut self) {}
 
+    /// Nothing to persist in memory
+    pub(super) fn ancestry_changed(&mut self, _lcid: LocalCid) {}
+
+    /// Listings are not remembered across restarts without a database
+    pub(super) async fn listed_children(&self, _cid: &str, _offset: usize, _limit: usize) -> Option<Vec<ListedEntry>> { None }
+    pub(super) async fn set_listed(&self, _cid: &str, _offset: usize, _children: &[ListedEntry], _complete: bool) {}
+
     pub fn folders(&self) -> HashMap<String, usize> {
         let mut folders = HashMap::new();
         for lcid in self.cids.left_values() {
@@ -66,6 +73,12 @@ impl DocumentIndexInner {
         for word in self.index.keys() {
             self.filter.add_word::<DocumentIndex>(word);
         }
+        for (key, value) in self.filters.keys() {
+            self.filter.add_word::<DocumentIndex>(&format!("{key}={value}"));
+        }
+        for word in self.scope_words() {
+            self.filter.add_word::<DocumentIndex>(&word);
+        }
         self.filter_needs_update = false;
     }
 
@@ -81,9 +94,9 @@ impl DocumentIndexInner {
         self.cids.insert(lcid, cid.to_owned());
         self.folders.remove(&lcid);
 
-        // Index by words
+        // Index by words and stems
         let word_count = doc.words.len() as f64;
-        for word in doc.words {
+        for word in doc.words.into_iter().chain(doc.stems) {
             let frequencies = self.index.entry(word.clone()).or_default();
             *frequencies.entry(lcid).or_insert(0.) += 1. / word_count as f32;
             self.filter.add_word::<DocumentIndex>(&word);
@@ -96,10 +109,26 @@ impl DocumentIndexInner {
         }
     }
 
+    pub async fn remove_cids(&mut self, lcids: HashSet<LocalCid>) -> Vec<String> {
+        self.index.retain(|_, frequencies| {
+            frequencies.retain(|lcid, _| !lcids.contains(lcid));
+            !frequencies.is_empty()
+        });
+        self.filters.retain(|_, docs| {
+            docs.retain(|lcid| !lcids.contains(lcid));
+            !docs.is_empty()
+        });
+        let removed = self.forget_cids(&lcids);
+
+        self.filter_needs_update = true;
+        self.update_filter().await;
+        removed
+    }
+
     // TODO: switching self to static may improve performance by a lot
     pub async fn search(&self, query: Arc<Query>) -> ResultStream<DocumentResult> {
         let matching_docs = match query.match_score(&self.filter) > 0 {
-            true => query.matching_docs(&self.index, &self.filters),
+            true => query.matching_docs(&self.index, &self.filters, |lcid| self.cids.get_by_left(&lcid).and_then(|cid| self.build_path(cid)).unwrap_or_default(), |name, value| self.scope_candidates(name, value)),
             false => Vec::new(),
         };
 
@@ -107,7 +136,7 @@ impl DocumentIndexInner {
             .into_iter()
             .filter_map(|lcid| self.cids.get_by_left(&lcid))
             .map(|cid| (cid, self.build_path(cid).unwrap_or_default()))
-            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
+            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.source)))
             .collect();
 
         Box::pin(DocumentResultStream { futures })
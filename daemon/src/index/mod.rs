@@ -6,10 +6,16 @@ pub(self) use crate::prelude::*;
 
 pub(self) const REFRESH_INTERVAL: u64 = 100;
 pub(self) const SWEEP_INTERVAL: u64 = 30;
+pub(self) const LOAD_BATCH_SIZE: usize = 50;
+pub(self) const LISTING_FLUSH_THRESHOLD: usize = 1000;
 
 mod index;
 mod inner_common;
+mod robots;
+mod exclusions;
 pub use index::*;
+pub(self) use robots::*;
+pub(self) use exclusions::*;
 
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
 mod inner_db;
@@ -21,6 +27,12 @@ mod inner_im;
 #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
 pub(self) use inner_im::*;
 
+/// Converts a cid to its v1 string representation, so that the same content always has the same key
+pub(self) fn normalize_cid(cid: impl AsRef<str>) -> Option<String> {
+    let cid = Cid::try_from(cid.as_ref()).ok()?;
+    let cid = cid.into_v1().ok()?;
+    Some(cid.to_string())
+}
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub struct LocalCid(pub u32);
@@ -38,13 +50,13 @@ impl Hash for LocalDid {
     }
 }
 
-pub async fn cid_to_result(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> Option<DocumentResult> {
-    let Ok(raw) = fetch_document(&config.ipfs_rpc, &cid).await else {return None};
+pub async fn cid_to_result(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, source: Arc<dyn ContentSource>) -> Option<DocumentResult> {
+    let Ok(raw) = source.fetch_document(&cid).await else {return None};
     generate_result(raw, cid, &query, paths)
 }
 
-fn cid_to_result_wrapper(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> Pin<Box<dyn Future<Output = Option<DocumentResult>> + Send>> {
-    Box::pin(cid_to_result(query, cid, paths, config))
+fn cid_to_result_wrapper(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, source: Arc<dyn ContentSource>) -> Pin<Box<dyn Future<Output = Option<DocumentResult>> + Send>> {
+    Box::pin(cid_to_result(query, cid, paths, source))
 }
 
 struct DocumentResultStream {
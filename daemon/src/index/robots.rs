@@ -0,0 +1,169 @@
+use super::*;
+
+/// Name of the file, at the root of pinned directories and DNS pins, that excludes some of their paths from indexing
+pub const ROBOTS_FILE_NAME: &str = "robots.txt";
+
+/// Path rules of a robots.txt file that apply to us.
+/// Like crawlers do, the most specific matching rule wins, and `Allow` wins ties.
+/// Patterns may contain `*` wildcards and end with `$` to match whole paths only.
+#[derive(Debug)]
+pub struct RobotsRules {
+    /// Path of the directory the file was found in, ending with a slash.
+    /// Patterns are matched against paths relative to it.
+    root: String,
+    /// Patterns with whether they allow or disallow the paths they match
+    rules: Vec<(String, bool)>,
+}
+
+impl Default for RobotsRules {
+    fn default() -> RobotsRules {
+        RobotsRules { root: String::from("/"), rules: Vec::new() }
+    }
+}
+
+impl RobotsRules {
+    /// Parses a robots.txt file found in the `root` directory.
+    /// Only the group for `admarus` is kept if there is one, or the group for `*` otherwise.
+    pub fn parse(raw: &str, root: &str) -> RobotsRules {
+        let mut specific_rules = Vec::new();
+        let mut generic_rules = Vec::new();
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in raw.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        group_agents.clear();
+                        in_rules = false;
+                    }
+                    group_agents.push(value.to_lowercase());
+                },
+                directive @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (value.to_owned(), directive == "allow");
+                    if group_agents.iter().any(|agent| agent == "admarus") {
+                        specific_rules.push(rule);
+                    } else if group_agents.iter().any(|agent| agent == "*") {
+                        generic_rules.push(rule);
+                    }
+                },
+                _ => (),
+            }
+        }
+        let rules = match specific_rules.is_empty() {
+            true => generic_rules,
+            false => specific_rules,
+        };
+        RobotsRules { root: root.to_owned(), rules }
+    }
+
+    /// Returns true if a path, starting with a slash and relative to the pinned directory, can be indexed
+    pub fn is_allowed(&self, path: &str) -> bool {
+        let Some(path) = path.strip_prefix(&self.root[..self.root.len() - 1]) else { return true };
+        self.rules
+            .iter()
+            .filter(|(pattern, _)| pattern_matches(pattern, path))
+            .max_by_key(|(pattern, allow)| (pattern.len(), *allow))
+            .map(|(_, allow)| *allow)
+            .unwrap_or(true)
+    }
+
+    /// Returns true if a path is that of the robots.txt file itself, which isn't worth indexing
+    pub fn is_robots_file(&self, path: &str) -> bool {
+        path.strip_prefix(&self.root) == Some(ROBOTS_FILE_NAME)
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = parts.next().and_then(|first| path.strip_prefix(first)) else { return false };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Fetches the robots.txt file of a pinned directory or DNS pin, if it has one.
+/// The `root` is the path of that directory in the pin.
+pub async fn fetch_robots_rules(source: &dyn ContentSource, root_cid: &str, root: &str) -> RobotsRules {
+    let Ok(cid) = source.resolve(&format!("/ipfs/{root_cid}/{ROBOTS_FILE_NAME}")).await else { return RobotsRules::default() };
+    match source.fetch_document(&cid).await {
+        Ok(raw) => RobotsRules::parse(&String::from_utf8_lossy(&raw), root),
+        Err(e) => {
+            warn!("Failed to fetch {ROBOTS_FILE_NAME} of {root_cid}: {e}");
+            RobotsRules::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/private", "/private/page.html"));
+        assert!(pattern_matches("/private", "/private-notes.html"));
+        assert!(!pattern_matches("/private", "/public/private"));
+        assert!(pattern_matches("/*.pdf", "/docs/report.pdf"));
+        assert!(pattern_matches("/*.pdf", "/docs/report.pdf.html"));
+        assert!(pattern_matches("/*.pdf$", "/docs/report.pdf"));
+        assert!(!pattern_matches("/*.pdf$", "/docs/report.pdf.html"));
+        assert!(pattern_matches("/a*b*c", "/axxbyyc/page"));
+        assert!(!pattern_matches("/a*b*c", "/axxcyyb"));
+        assert!(pattern_matches("/page$", "/page"));
+        assert!(!pattern_matches("/page$", "/page/"));
+        assert!(pattern_matches("*", "/anything"));
+    }
+
+    #[test]
+    fn test_group_selection() {
+        let raw = "User-agent: *\nDisallow: /generic/\n\nUser-agent: googlebot\nDisallow: /google/\n";
+        let rules = RobotsRules::parse(raw, "/");
+        assert!(!rules.is_allowed("/generic/page.html"));
+        assert!(rules.is_allowed("/google/page.html"));
+
+        let raw = "User-agent: *\nDisallow: /generic/\n\nUser-agent: bingbot\nUser-agent: Admarus\nDisallow: /specific/ # comment\nDisallow:\n";
+        let rules = RobotsRules::parse(raw, "/");
+        assert!(rules.is_allowed("/generic/page.html"));
+        assert!(!rules.is_allowed("/specific/page.html"));
+
+        let rules = RobotsRules::parse("User-agent: googlebot\nDisallow: /\n", "/");
+        assert!(rules.is_allowed("/page.html"));
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let raw = "User-agent: *\nDisallow: /docs/\nAllow: /docs/public/\nAllow: /page\nDisallow: /page\n";
+        let rules = RobotsRules::parse(raw, "/");
+        assert!(!rules.is_allowed("/docs/page.html"));
+        assert!(rules.is_allowed("/docs/public/page.html"));
+        assert!(rules.is_allowed("/page.html"), "Allow should win ties");
+        assert!(rules.is_allowed("/other.html"));
+
+        // Rules of DNS pins apply to paths relative to the site root only
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /docs/\n", "/dns-pin-example.com-0/");
+        assert!(!rules.is_allowed("/dns-pin-example.com-0/docs/page.html"));
+        assert!(rules.is_allowed("/dns-pin-example.com-0/page.html"));
+        assert!(rules.is_allowed("/docs/page.html"));
+        assert!(rules.is_robots_file("/dns-pin-example.com-0/robots.txt"));
+        assert!(!rules.is_robots_file("/robots.txt"));
+    }
+}
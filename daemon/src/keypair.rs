@@ -0,0 +1,104 @@
+use crate::prelude::*;
+use std::{fs, io::Write, path::Path};
+
+fn read_keypair(path: &str) -> Result<Keypair, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    Keypair::from_protobuf_encoding(&data).map_err(|e| e.to_string())
+}
+
+fn write_keypair(path: &str, keypair: &Keypair) {
+    let data = keypair.to_protobuf_encoding().expect("Failed to encode keypair");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).expect("Failed to open keypair file");
+    file.write_all(&data).expect("Failed to write keypair file");
+}
+
+/// Moves the current keypair file aside so that it can be restored if needed.
+/// Backups are timestamped, and numbered if needed, so that an earlier one is never overwritten.
+fn backup_keypair(path: &str) -> Result<(), String> {
+    if !Path::new(path).exists() {
+        return Ok(());
+    }
+    let time = now();
+    let backup_path = (0..100)
+        .map(|i| match i {
+            0 => format!("{path}.{time}.old"),
+            i => format!("{path}.{time}-{i}.old"),
+        })
+        .find(|backup_path| !Path::new(backup_path).exists())
+        .ok_or_else(|| format!("Too many keypair backups for {path}.{time}"))?;
+    fs::rename(path, &backup_path).map_err(|e| format!("Failed to move keypair to {backup_path}: {e}"))?;
+    warn!("Previous keypair moved to {backup_path}");
+    Ok(())
+}
+
+/// Path of the file marking that the keypair was rotated while [Args::rotate_keypair] is set.
+/// It is removed once the flag is unset, so that setting the flag again rotates the keypair again.
+fn rotation_marker_path(path: &str) -> String {
+    format!("{path}.rotated")
+}
+
+/// Backs up the current keypair and replaces it.
+/// The current keypair is kept if it can't be backed up.
+fn replace_keypair(path: &str, keypair: &Keypair) -> Result<(), String> {
+    backup_keypair(path)?;
+    write_keypair(path, keypair);
+    Ok(())
+}
+
+/// Loads the keypair of the node from [Args::keypair_path], generating it on first run.
+/// The keypair is replaced if [Args::import_keypair] or [Args::rotate_keypair] is set, only once for each.
+pub fn load_keypair(config: &Args) -> Keypair {
+    let path = config.keypair_path.as_str();
+    let marker_path = rotation_marker_path(path);
+
+    if let Some(import_path) = &config.import_keypair {
+        let keypair = read_keypair(import_path).unwrap_or_else(|e| panic!("Failed to import keypair from {import_path}: {e}"));
+        let current = read_keypair(path).ok().and_then(|current| current.to_protobuf_encoding().ok());
+        if current.is_some() && current == keypair.to_protobuf_encoding().ok() {
+            warn!("Keypair from {import_path} was already imported. You can remove import_keypair from your config.");
+            return keypair;
+        }
+        match replace_keypair(path, &keypair) {
+            Ok(()) => {
+                info!("Imported keypair from {import_path}");
+                return keypair;
+            },
+            Err(e) => error!("Keypair from {import_path} not imported: {e}"),
+        }
+    } else if config.rotate_keypair {
+        if Path::new(&marker_path).exists() {
+            warn!("Keypair was already rotated. You can remove rotate_keypair from your config.");
+        } else {
+            warn!("Rotating keypair. Your peer id will change and other peers will forget about you.");
+            let keypair = Keypair::generate_ed25519();
+            match replace_keypair(path, &keypair) {
+                Ok(()) => {
+                    if let Err(e) = fs::write(&marker_path, now().to_string()) {
+                        error!("Failed to write {marker_path}: {e}. The keypair will be rotated again on next restart unless rotate_keypair is removed.");
+                    }
+                    return keypair;
+                },
+                Err(e) => error!("Keypair not rotated: {e}"),
+            }
+        }
+    } else if Path::new(&marker_path).exists() {
+        if let Err(e) = fs::remove_file(&marker_path) {
+            warn!("Failed to remove {marker_path}: {e}");
+        }
+    }
+
+    if !Path::new(path).exists() {
+        info!("No keypair found at {path}, generating a new one");
+        let keypair = Keypair::generate_ed25519();
+        write_keypair(path, &keypair);
+        return keypair;
+    }
+
+    read_keypair(path).unwrap_or_else(|e| panic!("Failed to read keypair at {path}: {e}"))
+}
@@ -0,0 +1,67 @@
+//! Identifies the language of documents from their text.
+//! Detection relies on trigram profiles, which are reliable on a few sentences of text.
+//! The declared language of a document is only used as a hint, as it is often missing or wrong.
+
+use whatlang::Lang;
+
+/// Texts shorter than this (in bytes) are too short for their language to be detected
+const MIN_DETECTION_LENGTH: usize = 40;
+
+/// Only the beginning of texts is used for detection, which is more than enough to be accurate
+pub const MAX_DETECTION_LENGTH: usize = 5_000;
+
+/// Returns the ISO 639-1 code of a language
+fn iso_639_1(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Epo => "eo", Lang::Eng => "en", Lang::Rus => "ru", Lang::Cmn => "zh", Lang::Spa => "es",
+        Lang::Por => "pt", Lang::Ita => "it", Lang::Ben => "bn", Lang::Fra => "fr", Lang::Deu => "de",
+        Lang::Ukr => "uk", Lang::Kat => "ka", Lang::Ara => "ar", Lang::Hin => "hi", Lang::Jpn => "ja",
+        Lang::Heb => "he", Lang::Yid => "yi", Lang::Pol => "pl", Lang::Amh => "am", Lang::Jav => "jv",
+        Lang::Kor => "ko", Lang::Nob => "no", Lang::Dan => "da", Lang::Swe => "sv", Lang::Fin => "fi",
+        Lang::Tur => "tr", Lang::Nld => "nl", Lang::Hun => "hu", Lang::Ces => "cs", Lang::Ell => "el",
+        Lang::Bul => "bg", Lang::Bel => "be", Lang::Mar => "mr", Lang::Kan => "kn", Lang::Ron => "ro",
+        Lang::Slv => "sl", Lang::Hrv => "hr", Lang::Srp => "sr", Lang::Mkd => "mk", Lang::Lit => "lt",
+        Lang::Lav => "lv", Lang::Est => "et", Lang::Tam => "ta", Lang::Vie => "vi", Lang::Urd => "ur",
+        Lang::Tha => "th", Lang::Guj => "gu", Lang::Uzb => "uz", Lang::Pan => "pa", Lang::Aze => "az",
+        Lang::Ind => "id", Lang::Tel => "te", Lang::Pes => "fa", Lang::Mal => "ml", Lang::Ori => "or",
+        Lang::Mya => "my", Lang::Nep => "ne", Lang::Sin => "si", Lang::Khm => "km", Lang::Tuk => "tk",
+        Lang::Aka => "ak", Lang::Zul => "zu", Lang::Sna => "sn", Lang::Afr => "af", Lang::Lat => "la",
+        Lang::Slk => "sk", Lang::Cat => "ca", Lang::Tgl => "tl", Lang::Hye => "hy",
+    }
+}
+
+/// Turns a language tag such as "en-US" into the code used for the `lang` filter
+pub fn normalize_lang_tag(tag: &str) -> Option<String> {
+    let code = tag.trim().split(['-', '_']).next()?.to_lowercase();
+    let code = match code.as_str() {
+        "nb" | "nn" => String::from("no"),
+        "iw" => String::from("he"),
+        "" => return None,
+        _ => code,
+    };
+    Some(code)
+}
+
+/// Detects the language of a text and returns its ISO 639-1 code.
+/// The declared language is used when the text is too short or ambiguous, such as between close languages.
+pub fn detect_language(text: &str, declared: Option<&str>) -> Option<String> {
+    let declared = declared.and_then(normalize_lang_tag);
+
+    let mut end = text.len().min(MAX_DETECTION_LENGTH);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = text[..end].trim();
+    if text.len() < MIN_DETECTION_LENGTH {
+        return declared;
+    }
+
+    let Some(info) = whatlang::detect(text) else { return declared };
+    let detected = iso_639_1(info.lang());
+    match (info.is_reliable(), declared) {
+        (true, _) => Some(detected.to_owned()),
+        (false, Some(declared)) => Some(declared),
+        (false, None) if info.confidence() >= 0.5 => Some(detected.to_owned()),
+        (false, None) => None,
+    }
+}
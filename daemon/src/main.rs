@@ -10,10 +10,16 @@ mod rpc_census;
 mod documents;
 mod api;
 mod node;
+mod keypair;
 mod clap;
 mod swarm;
 mod query;
 mod dns_pins;
+mod sources;
+mod tokenizer;
+mod stemming;
+mod language;
+mod dates;
 
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
 mod database;
@@ -39,14 +45,15 @@ async fn main() {
         warn!("The webui doesn't currently support custom api addresses, so you probably don't want to change this.")
     }
 
-    let index = DocumentIndex::new(Arc::clone(&config)).await;
+    let (source, cars) = open_source(&config);
+    let index = DocumentIndex::new(Arc::clone(&config), source).await;
 
     let (node, keypair) = Node::init(Arc::clone(&config), index.clone()).await;
     let node = node.run();
     
     let search_park = Arc::new(SearchPark::new());
 
-    let f1 = serve_api(Arc::clone(&config), index.clone(), search_park, node.clone());
+    let f1 = serve_api(Arc::clone(&config), index.clone(), cars, search_park, node.clone());
     let f2 = update_census_task(node.clone(), index.clone(), keypair.clone(), Arc::clone(&config));
     let f3 = maintain_swarm_task(node.clone(), Arc::clone(&config));
     let f4 = cleanup_db_task(node.clone());
@@ -42,7 +42,7 @@ pub struct Node {
 
 impl Node {
     pub async fn init(config: Arc<Args>, index: DocumentIndex) -> (Node, Keypair) {
-        let keypair = Keypair::generate_ed25519();
+        let keypair = load_keypair(&config);
         let peer_id = PeerId::from(keypair.public());
         info!("Local peer id: {peer_id}");
 
@@ -6,9 +6,15 @@ pub use crate::{
     documents::*,
     api::*,
     node::*,
+    keypair::*,
     clap::*,
     swarm::*,
     dns_pins::*,
+    sources::*,
+    tokenizer::*,
+    stemming::*,
+    language::*,
+    dates::*,
     query::*,
 };
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
@@ -20,7 +26,6 @@ pub use log::{info, warn, error, debug, trace};
 pub use kamilata::{prelude::*, db::TooManyLeechers, behaviour::KamilataEvent, store::{ResultStream, ResultStreamBuilderFut}};
 pub use serde::{Serialize, Deserialize};
 pub use async_trait::async_trait;
-//pub use schemas::{traits::Schema, types::{Text as SchemaText, URL as SchemaUrl, Types as StructuredData}};
 pub use std::{
     time::{SystemTime, Duration, Instant, UNIX_EPOCH},
     collections::{HashMap, HashSet}, sync::Arc, pin::Pin, future::Future, cmp::Ordering, iter::zip, net::SocketAddr, str::FromStr, io::Error as IoError,
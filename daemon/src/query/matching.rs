@@ -4,7 +4,8 @@ impl SearchQuery<FILTER_SIZE> for Query {
     type ParsingError = serde_json::Error;
 
     fn match_score(&self, filter: &Filter<FILTER_SIZE>) -> u32 {
-        self.root.match_score(filter)
+        let lang = self.lang();
+        self.root.match_score(filter, lang.as_deref())
     }
 
     fn to_bytes(&self) -> Vec<u8> {
@@ -17,16 +18,28 @@ impl SearchQuery<FILTER_SIZE> for Query {
 }
 
 impl QueryComp {
-    fn match_score(&self, filter: &Filter<FILTER_SIZE>) -> u32 {
+    fn match_score(&self, filter: &Filter<FILTER_SIZE>, lang: Option<&str>) -> u32 {
         match self {
-            QueryComp::Word(word) => filter.get_word::<DocumentIndex>(word) as u32,
+            QueryComp::Word(word) => {
+                let matches = filter.get_word::<DocumentIndex>(word) || query_stem_key(word, lang).map(|key| filter.get_word::<DocumentIndex>(&key)).unwrap_or(false);
+                matches as u32
+            },
+            // Paths of folders are in the filter, so documents are found from the folder they are in
+            QueryComp::Filter { name, value } if name == "path" => {
+                let value = value.trim_end_matches('/');
+                let parent = value.rsplit_once('/').map(|(parent, _)| parent).unwrap_or_default();
+                let matches = value.is_empty()
+                    || filter.get_word::<DocumentIndex>(&format!("path={value}"))
+                    || (!parent.is_empty() && filter.get_word::<DocumentIndex>(&format!("path={parent}")));
+                matches as u32
+            },
             QueryComp::Filter { name, value } => filter.get_word::<DocumentIndex>(&format!("{name}={value}")) as u32,
-            QueryComp::Not(comp) => match comp.match_score(filter) { 0 => 1, _ => 0 },
+            QueryComp::Not(comp) => match comp.match_score(filter, lang) { 0 => 1, _ => 0 },
             QueryComp::NAmong { n, among } => {
                 let mut sum = 0;
                 let mut matching = 0;
                 for comp in among {
-                    let score = comp.match_score(filter);
+                    let score = comp.match_score(filter, lang);
                     sum += score;
                     if score > 0 {
                         matching += 1;
@@ -40,13 +53,14 @@ impl QueryComp {
         }
     }
 
-    fn match_score_index(&self, lcid: LocalCid, index: &HashMap<String, HashMap<LocalCid, f32>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> f32 {
+    fn match_score_index(&self, lcid: LocalCid, terms: &HashMap<&String, HashSet<LocalCid>>, filters: &HashMap<(String, String), Vec<LocalCid>>, paths: &[Vec<String>], lang: Option<&str>) -> f32 {
         match self {
-            QueryComp::Word(word) => index.get(word).map(|l| l.contains_key(&lcid) as usize as f32).unwrap_or(0.0),
+            QueryComp::Word(word) => terms.get(word).map(|l| l.contains(&lcid) as usize as f32 * word_lists::word_rarity(word, lang) as f32).unwrap_or(0.0),
+            QueryComp::Filter { name, value } if SCOPE_FILTERS.contains(&name.as_str()) => paths.iter().any(|path| scope_matches(name, value, path)) as usize as f32,
             QueryComp::Filter { name, value } => filters.get(&(name.clone(), value.clone())).map(|l| l.contains(&lcid) as usize as f32).unwrap_or(0.0),
-            QueryComp::Not(comp) => if comp.match_score_index(lcid, index, filters) == 0.0 { 1.0 } else { 0.0 }
+            QueryComp::Not(comp) => if comp.match_score_index(lcid, terms, filters, paths, lang) == 0.0 { 1.0 } else { 0.0 }
             QueryComp::NAmong { n, among } => {
-                let mut scores = among.iter().map(|comp| comp.match_score_index(lcid, index, filters)).collect::<Vec<_>>();
+                let mut scores = among.iter().map(|comp| comp.match_score_index(lcid, terms, filters, paths, lang)).collect::<Vec<_>>();
                 scores.retain(|score| *score > 0.0);
                 scores.sort_by(|score1, score2| score2.partial_cmp(score1).unwrap_or(std::cmp::Ordering::Equal));
                 scores.truncate(*n);
@@ -67,23 +81,78 @@ impl QueryComp {
 }
 
 impl Query {
-    pub fn matching_docs(&self, index: &HashMap<String, HashMap<LocalCid, f32>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> Vec<LocalCid> {
+    /// Returns the language of the query, from its `lang` filter or detected from its terms.
+    /// Queries too short for detection have no language, and are then matched without stems.
+    pub fn lang(&self) -> Option<String> {
+        if let Some(lang) = self.lang_filter() {
+            return Some(lang.to_owned());
+        }
+        let text = self.positive_terms().into_iter().map(|term| term.as_str()).collect::<Vec<_>>().join(" ");
+        detect_language(&text, None)
+    }
+
+    /// Lists the index keys that are relevant to this query: its terms and their stems
+    pub fn index_keys(&self) -> Vec<String> {
+        let lang = self.lang();
+        let mut keys = Vec::new();
+        for term in self.terms() {
+            keys.push(term.to_owned());
+            keys.extend(query_stem_key(term, lang.as_deref()));
+        }
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Lists the documents matching the query, best first.
+    /// Paths are only built for queries with `site:` or `path:` operators, which are checked against them.
+    /// Queries made of scope operators only have their candidates listed by `scope_candidates`.
+    pub fn matching_docs(&self, index: &HashMap<String, HashMap<LocalCid, f32>>, filters: &HashMap<(String, String), Vec<LocalCid>>, build_paths: impl Fn(LocalCid) -> Vec<Vec<String>>, scope_candidates: impl Fn(&str, &str) -> Vec<LocalCid>) -> Vec<LocalCid> {
         let positive_terms = self.positive_terms();
         let positive_filters = self.positive_filters();
+        let is_scope_only = positive_terms.is_empty() && positive_filters.iter().all(|(name, _)| SCOPE_FILTERS.contains(&name.as_str()));
+
+        // Documents containing each term, either as is or under the same stem
+        let lang = self.lang();
+        let mut terms: HashMap<&String, HashSet<LocalCid>> = HashMap::new();
+        for term in self.terms() {
+            let docs = terms.entry(term).or_default();
+            for key in std::iter::once(term.to_owned()).chain(query_stem_key(term, lang.as_deref())) {
+                if let Some(new_docs) = index.get(&key) {
+                    docs.extend(new_docs.keys());
+                }
+            }
+        }
 
         let mut candidates: HashSet<LocalCid> = HashSet::new();
         for positive_term in positive_terms {
-            if let Some(new_candidates) = index.get(positive_term) {
-                candidates.extend(new_candidates.keys());
+            if let Some(new_candidates) = terms.get(positive_term) {
+                candidates.extend(new_candidates);
             }
         }
-        for (name, value) in positive_filters {
-            if let Some(new_candidates) = filters.get(&(name.clone(), value.clone())) {
+        for (name, value) in &positive_filters {
+            if let Some(new_candidates) = filters.get(&(name.to_string(), value.to_string())) {
                 candidates.extend(new_candidates);
             }
         }
+        if is_scope_only {
+            if let Some((name, value)) = positive_filters.first() {
+                candidates.extend(scope_candidates(name, value));
+            }
+        }
 
-        let mut matching = candidates.into_iter().map(|lcid| (self.root.match_score_index(lcid, index, filters), lcid)).filter(|(score, _)| *score > 0.0).collect::<Vec<_>>();
+        let is_scoped = self.is_scoped();
+        let mut matching = candidates
+            .into_iter()
+            .map(|lcid| {
+                let paths = match is_scoped {
+                    true => build_paths(lcid),
+                    false => Vec::new(),
+                };
+                (self.root.match_score_index(lcid, &terms, filters, &paths, lang.as_deref()), lcid)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect::<Vec<_>>();
         matching.sort_by(|(score1, _), (score2, _)| score2.partial_cmp(score1).unwrap_or(std::cmp::Ordering::Equal));
         matching.into_iter().map(|(_, lcid)| lcid).collect::<Vec<_>>()
     }
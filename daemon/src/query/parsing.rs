@@ -7,12 +7,49 @@ pub struct Parser {
 
 }
 
-pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
+/// Normalizes a word of the query the same way documents are tokenized.
+/// Words the tokenizer splits into multiple tokens (such as CJK text) require all of them to match.
+fn build_word_comp(word: &str) -> QueryComp {
+    let mut tokens = tokenize(word);
+    match tokens.len() {
+        0 => QueryComp::Word(normalize_word(word)),
+        1 => QueryComp::Word(tokens.remove(0)),
+        n => QueryComp::NAmong {
+            n,
+            among: tokens.into_iter().map(QueryComp::Word).collect(),
+        },
+    }
+}
+
+/// Turns a date comparison such as `year>2020` into the date buckets it covers.
+/// Invalid dates match nothing.
+fn build_date_range_comp(name: &str, op: &str, value: &str, today: u32) -> QueryComp {
+    let period = parse_date_period(value).filter(|_| DATE_FILTERS.contains(&name) || name == "date");
+    let range = period.and_then(|(first, last)| match op {
+        ">" => Some((last.checked_add(1)?, u32::MAX)),
+        ">=" => Some((first, u32::MAX)),
+        "<" => Some((0, first.checked_sub(1)?)),
+        "<=" => Some((0, last)),
+        _ => Some((first, last)),
+    });
+    QueryComp::NAmong {
+        n: 1,
+        among: range
+            .map(|(first, last)| date_range_filters(first, last, today))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| QueryComp::Filter { name: name.to_owned(), value })
+            .collect(),
+    }
+}
+
+/// Builds a query component, resolving relative dates from `today` (in days since the Unix epoch)
+pub(super) fn build_comp(ident: IdentRef<Ident>, today: u32) -> QueryComp {
     match ident.as_rule() {
         Rule::word_comp => {
             let word = ident.children().next().unwrap();
             let word = word.children().map(|c| c.as_str()).collect::<Vec<_>>().join("");
-            QueryComp::Word(word.to_lowercase()) // TODO: normalization (cf meilisearch)
+            build_word_comp(&word)
         },
         Rule::and_comp => {
             let mut children = ident.children().collect::<Vec<_>>();
@@ -27,7 +64,7 @@ pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
             }
             QueryComp::NAmong {
                 n: children.len(),
-                among: children.into_iter().map(build_comp).collect::<Vec<_>>(),
+                among: children.into_iter().map(|c| build_comp(c, today)).collect::<Vec<_>>(),
             }
         },
         Rule::or_comp => {
@@ -43,38 +80,76 @@ pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
             }
             QueryComp::NAmong {
                 n: 1,
-                among: children.into_iter().map(build_comp).collect::<Vec<_>>(),
+                among: children.into_iter().map(|c| build_comp(c, today)).collect::<Vec<_>>(),
             }
         },
         Rule::quick_or_comp => {
             let words = ident.children().map(|c| 
-                c.children().map(|c| c.as_str()).collect::<Vec<_>>().join("").to_lowercase()
+                c.children().map(|c| c.as_str()).collect::<Vec<_>>().join("")
             );
             QueryComp::NAmong {
                 n: 1,
-                among: words.map(QueryComp::Word).collect::<Vec<_>>(),
+                among: words.map(|w| build_word_comp(&w)).collect::<Vec<_>>(),
             }
         },
         Rule::not_comp => {
             let child = ident.children().next().unwrap();
-            QueryComp::Not(Box::new(build_comp(child)))
+            QueryComp::Not(Box::new(build_comp(child, today)))
         },
         Rule::namong_comp => {
             let mut children = ident.children();
             let n = children.next().unwrap().as_str().parse::<usize>().unwrap();
             QueryComp::NAmong {
                 n,
-                among: children.map(build_comp).collect::<Vec<_>>(),
+                among: children.map(|c| build_comp(c, today)).collect::<Vec<_>>(),
             }
         },
         Rule::filter_comp => {
             let mut children = ident.children();
             let name = children.next().unwrap().children().map(|c| c.as_str()).collect::<Vec<_>>().join("");
             let value = children.next().unwrap().children().map(|c| c.as_str()).collect::<Vec<_>>().join("");
+            match name.as_str() {
+                "after" => build_date_range_comp("date", ">", &value, today),
+                "before" => build_date_range_comp("date", "<", &value, today),
+                "date" => build_date_range_comp("date", "=", &value, today),
+                SCHEMA_FILTER => QueryComp::Filter {
+                    // Types are indexed with their schema.org capitalization
+                    value: SCHEMA_TYPES.iter().find(|ty| ty.eq_ignore_ascii_case(&value)).map(|ty| ty.to_string()).unwrap_or(value),
+                    name,
+                },
+                _ => QueryComp::Filter {
+                    name,
+                    value,
+                }
+            }
+        },
+        Rule::scope_comp => {
+            let mut children = ident.children();
+            let name = children.next().unwrap().as_str().to_owned();
+            let value = children.next().unwrap().as_str();
+            let value = match name.as_str() {
+                "site" => value.trim_end_matches('.').to_lowercase(),
+                _ => format!("/{}", value.trim_start_matches('/')),
+            };
             QueryComp::Filter {
                 name,
                 value,
             }
+        },
+        Rule::scoped_comp => {
+            // Scopes following a query restrict it, as if they were joined with AND
+            let children = ident.children().collect::<Vec<_>>();
+            QueryComp::NAmong {
+                n: children.len(),
+                among: children.into_iter().map(|c| build_comp(c, today)).collect::<Vec<_>>(),
+            }
+        },
+        Rule::range_comp => {
+            let mut children = ident.children();
+            let name = children.next().unwrap().as_str();
+            let op = children.next().unwrap().as_str();
+            let value = children.next().unwrap().as_str();
+            build_date_range_comp(name, op, value, today)
         }
         _ => unreachable!()
     }
@@ -82,9 +157,14 @@ pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
 
 impl Query {
     pub fn parse(query: &str) -> Result<Query, Error> {
+        Query::parse_at(query, today())
+    }
+
+    /// Parses a query as if it was written on `today` (in days since the Unix epoch)
+    pub fn parse_at(query: &str, today: u32) -> Result<Query, Error> {
         let idents = Parser::parse_query(query)?;
         Ok(Query {
-            root: build_comp(idents.root())
+            root: build_comp(idents.root(), today)
         })
     }
 }
@@ -104,3 +184,62 @@ fn test() {
     let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
     println!("{:#?}", output);
 }
+
+#[test]
+fn test_normalization() {
+    let input = "Été";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert_eq!(output.root, QueryComp::Word(String::from("ete")));
+
+    let input = "東京都";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert_eq!(output.root, QueryComp::NAmong { n: 2, among: vec![QueryComp::Word(String::from("東京")), QueryComp::Word(String::from("京都"))] });
+
+    let input = "schema=recipe";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert_eq!(output.root, QueryComp::Filter { name: String::from("schema"), value: String::from("Recipe") });
+}
+
+#[test]
+fn test_date_ranges() {
+    let today = parse_date("2023-10-17").unwrap();
+    let filters = |input: &str| {
+        let output = Query::parse_at(input, today).unwrap_or_else(|e| {e.print(input); panic!()});
+        output.filters().into_iter().map(|(name, value)| format!("{name}={value}")).collect::<Vec<_>>()
+    };
+
+    assert_eq!(filters("year<=1971"), vec!["year=1970", "year=1971"]);
+    assert_eq!(filters("before=1970-03-03"), vec!["month=1970-01", "month=1970-02", "day=1970-03-01", "day=1970-03-02"]);
+    assert_eq!(filters("date=2024-02"), vec!["month=2024-02"]);
+    assert_eq!(filters("year=2024"), vec!["year=2024"]);
+    assert_eq!(filters("date=2023-13"), Vec::<String>::new());
+
+    let after = filters("word AND after=2023-10-15");
+    assert_eq!(after[..18], ["day=2023-10-16", "day=2023-10-17", "day=2023-10-18", "day=2023-10-19", "day=2023-10-20", "day=2023-10-21",
+        "day=2023-10-22", "day=2023-10-23", "day=2023-10-24", "day=2023-10-25", "day=2023-10-26", "day=2023-10-27", "day=2023-10-28",
+        "day=2023-10-29", "day=2023-10-30", "day=2023-10-31", "month=2023-11", "month=2023-12"]);
+    assert_eq!(after[18], "year=2024");
+    assert_eq!(after.len(), 19);
+
+    // Comparisons are only allowed on dates
+    assert!(Query::parse_at("a<b", today).is_err());
+    assert!(Query::parse_at("year<abc", today).is_err());
+}
+
+#[test]
+fn test_scopes() {
+    let input = "rust async site:Docs.Example.com path:guide/";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert_eq!(output.root, QueryComp::NAmong { n: 3, among: vec![
+        QueryComp::NAmong { n: 1, among: vec![QueryComp::Word(String::from("rust")), QueryComp::Word(String::from("async"))] },
+        QueryComp::Filter { name: String::from("site"), value: String::from("docs.example.com") },
+        QueryComp::Filter { name: String::from("path"), value: String::from("/guide/") },
+    ] });
+
+    let path = ["blog.example.com", "posts", "2023", "index.html"].map(String::from);
+    assert!(scope_matches("site", "example.com", &path));
+    assert!(!scope_matches("site", "ample.com", &path));
+    assert!(scope_matches("path", "/posts/", &path));
+    assert!(scope_matches("path", "/posts/2023", &path));
+    assert!(!scope_matches("path", "/post", &path));
+}
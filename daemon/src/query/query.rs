@@ -2,6 +2,27 @@
 
 use crate::prelude::*;
 
+/// Filters that scope results to a domain (`site:example.com`) or a folder (`path:/docs/`).
+/// They are checked against the paths of documents rather than stored in the index.
+pub const SCOPE_FILTERS: [&str; 2] = ["site", "path"];
+
+/// Checks a path built by `build_path` against a scope filter
+pub fn scope_matches(name: &str, value: &str, path: &[String]) -> bool {
+    let Some(root) = path.first() else { return false };
+    match name {
+        "site" => {
+            let root = root.to_lowercase();
+            root == value || root.strip_suffix(value).map(|sub| sub.ends_with('.')).unwrap_or(false)
+        },
+        "path" => {
+            let value = value.trim_end_matches('/');
+            let doc_path = format!("/{}", path[1..].join("/"));
+            doc_path == value || doc_path.starts_with(&format!("{value}/"))
+        },
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Query {
     #[serde(flatten)]
@@ -17,13 +38,29 @@ impl Query {
         self.root.terms()
     }
 
+    /// Splits weight between terms according to their rarity in the language requested by the `lang=` filter
     pub fn weighted_terms(&self) -> Vec<(String, f64)> {
-        self.root.clone_only_words().map(|r| r.weighted_terms(1.0)).unwrap_or_default()
+        let lang = self.lang_filter().map(|lang| lang.as_str());
+        self.root.clone_only_words().map(|r| r.weighted_terms(1.0, lang)).unwrap_or_default()
     }
 
     pub fn positive_filters(&self) -> Vec<(&String, &String)> {
         self.root.positive_filters()
     }
+
+    pub fn filters(&self) -> Vec<(&String, &String)> {
+        self.root.filters()
+    }
+
+    /// Returns the language requested by a `lang=` filter
+    pub fn lang_filter(&self) -> Option<&String> {
+        self.positive_filters().into_iter().find(|(name, _)| name.as_str() == "lang").map(|(_, lang)| lang)
+    }
+
+    /// Returns true if the query contains `site:` or `path:` operators
+    pub fn is_scoped(&self) -> bool {
+        self.filters().iter().any(|(name, _)| SCOPE_FILTERS.contains(&name.as_str()))
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -88,12 +125,33 @@ impl QueryComp {
         }
     }
 
-    pub fn weighted_terms(&self, weight: f64) -> Vec<(String, f64)> {
+    pub fn weighted_terms(&self, weight: f64, lang: Option<&str>) -> Vec<(String, f64)> {
         match self {
             QueryComp::Word(word) => vec![(word.to_string(), weight)],
             QueryComp::Filter { .. } => panic!("QueryComp::weighted_terms() called on filter"),
             QueryComp::Not(_) => panic!("QueryComp::weighted_terms() called on not"),
-            QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.weighted_terms(weight/(among.len() as f64))).collect::<Vec<_>>(), // FIXME: handle 0
+            QueryComp::NAmong { among, .. } => {
+                // Weight is split according to rarity, so that common words don't weigh as much as the others
+                let rarities = among.iter().map(|c| c.rarity(lang)).collect::<Vec<_>>();
+                let total_rarity = rarities.iter().sum::<f64>();
+                if total_rarity <= 0.0 {
+                    return Vec::new();
+                }
+                among.iter().zip(rarities).flat_map(|(c, rarity)| c.weighted_terms(weight * rarity / total_rarity, lang)).collect::<Vec<_>>()
+            },
+        }
+    }
+
+    /// Estimates how rare the words of this component are in a language, from 0.0 to 1.0
+    pub fn rarity(&self, lang: Option<&str>) -> f64 {
+        match self {
+            QueryComp::Word(word) => word_lists::word_rarity(word, lang),
+            QueryComp::Filter { .. } => 0.0,
+            QueryComp::Not(_) => 0.0,
+            QueryComp::NAmong { among, .. } => match among.is_empty() {
+                true => 0.0,
+                false => among.iter().map(|c| c.rarity(lang)).sum::<f64>() / among.len() as f64,
+            },
         }
     }
 
@@ -105,4 +163,13 @@ impl QueryComp {
             QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.positive_filters()).collect::<Vec<_>>(),
         }
     }
+
+    pub fn filters(&self) -> Vec<(&String, &String)> {
+        match self {
+            QueryComp::Word(_) => Vec::new(),
+            QueryComp::Filter { name, value } => vec![(name, value)],
+            QueryComp::Not(comp) => comp.filters(),
+            QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.filters()).collect::<Vec<_>>(),
+        }
+    }
 }
@@ -8,4 +8,7 @@ pub use result::*;
 mod word_count;
 pub use word_count::*;
 
+mod structured_data;
+pub use structured_data::*;
+
 mod validation;
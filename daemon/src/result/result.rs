@@ -8,6 +8,27 @@ pub struct FaviconDescriptor {
     pub sizes: String,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+/// Image or video found in a document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MediaDescriptor {
+    pub kind: MediaKind,
+    /// Address of the media, as found in the document.
+    /// It is kept unresolved so that results can be verified, and resolved against the document path on display.
+    pub src: String,
+    /// Alt text of images, or label of videos
+    pub alt: Option<String>,
+    /// Content of the figcaption of the enclosing figure
+    pub caption: Option<String>,
+    /// Text surrounding the media in the document
+    pub context: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentResult {
     pub cid: String,
@@ -30,6 +51,19 @@ pub struct DocumentResult {
     /// Required if description is not present
     /// Size limit: 5kB
     pub extract: Option<String>,
+    /// Image to show along the result, from OpenGraph or Twitter card metadata.
+    /// It is kept unresolved, like the sources of media.
+    /// Size limit: 1kB
+    #[serde(default)]
+    pub preview_image: Option<String>,
+    /// Name of the website the document belongs to, from OpenGraph metadata
+    /// Size limit: 200B
+    #[serde(default)]
+    pub site_name: Option<String>,
+    /// Publication date, as found in OpenGraph metadata
+    /// Size limit: 100B
+    #[serde(default)]
+    pub published: Option<String>,
 
     /// Each query term is mapped to the number of times it appears in the document.
     /// Along with `word_count`, this can be used to calculate the tf-idf score.
@@ -37,8 +71,14 @@ pub struct DocumentResult {
     /// The number of words in the document.
     pub word_count: WordCount,
 
-    /// Structured data extracted from the document.
-    pub structured_data: Vec<()>,
+    /// Images and videos of the document.
+    /// Size limit: 20 items, 5kB in total
+    #[serde(default)]
+    pub media: Vec<MediaDescriptor>,
+
+    /// Schema.org items extracted from the document.
+    /// Size limit: 10 items, 500B per property and 5kB in total
+    pub structured_data: Vec<StructuredData>,
 
     /// Present if daemon supports the language of the document.
     /// Is intended to represent the share of words in the document that are common in that language.
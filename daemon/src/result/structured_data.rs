@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+/// Size limit of each text property of structured data (in bytes)
+pub const MAX_STRUCTURED_TEXT_LENGTH: usize = 500;
+/// Maximum number of structured data items in a result
+pub const MAX_STRUCTURED_DATA_ITEMS: usize = 10;
+/// Size limit of all structured data items of a result (in bytes)
+pub const MAX_STRUCTURED_DATA_SIZE: usize = 5_000;
+/// Name of the filter under which documents are indexed by the types of their structured data items (`schema=Recipe`)
+pub const SCHEMA_FILTER: &str = "schema";
+/// Schema.org types that structured data items can have
+pub const SCHEMA_TYPES: [&str; 6] = ["Article", "Product", "Recipe", "Person", "Organization", "Event"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ArticleData {
+    pub headline: String,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub date_published: Option<String>,
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProductData {
+    pub name: String,
+    pub description: Option<String>,
+    pub brand: Option<String>,
+    pub image: Option<String>,
+    pub price: Option<String>,
+    pub price_currency: Option<String>,
+    pub rating: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RecipeData {
+    pub name: String,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub image: Option<String>,
+    pub total_time: Option<String>,
+    pub recipe_yield: Option<String>,
+    pub rating: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PersonData {
+    pub name: String,
+    pub job_title: Option<String>,
+    pub image: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OrganizationData {
+    pub name: String,
+    pub description: Option<String>,
+    pub logo: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EventData {
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Schema.org item found in a document, as JSON-LD or microdata.
+/// Only the types and properties that results can display are kept.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum StructuredData {
+    Article(ArticleData),
+    Product(ProductData),
+    Recipe(RecipeData),
+    Person(PersonData),
+    Organization(OrganizationData),
+    Event(EventData),
+}
+
+impl StructuredData {
+    /// Returns the name of the schema.org type
+    pub fn schema_type(&self) -> &'static str {
+        match self {
+            StructuredData::Article(_) => SCHEMA_TYPES[0],
+            StructuredData::Product(_) => SCHEMA_TYPES[1],
+            StructuredData::Recipe(_) => SCHEMA_TYPES[2],
+            StructuredData::Person(_) => SCHEMA_TYPES[3],
+            StructuredData::Organization(_) => SCHEMA_TYPES[4],
+            StructuredData::Event(_) => SCHEMA_TYPES[5],
+        }
+    }
+
+    /// Lists the text properties of the item
+    pub fn texts(&self) -> Vec<&str> {
+        let (required, optional) = match self {
+            StructuredData::Article(a) => (&a.headline, vec![&a.description, &a.author, &a.date_published, &a.image]),
+            StructuredData::Product(p) => (&p.name, vec![&p.description, &p.brand, &p.image, &p.price, &p.price_currency]),
+            StructuredData::Recipe(r) => (&r.name, vec![&r.description, &r.author, &r.image, &r.total_time, &r.recipe_yield]),
+            StructuredData::Person(p) => (&p.name, vec![&p.job_title, &p.image, &p.url]),
+            StructuredData::Organization(o) => (&o.name, vec![&o.description, &o.logo, &o.url]),
+            StructuredData::Event(e) => (&e.name, vec![&e.description, &e.start_date, &e.end_date, &e.location]),
+        };
+        std::iter::once(required.as_str()).chain(optional.into_iter().flatten().map(|text| text.as_str())).collect()
+    }
+
+    /// Returns the sum of the sizes of text properties (in bytes)
+    pub fn size(&self) -> usize {
+        self.texts().iter().map(|text| text.len()).sum()
+    }
+
+    pub fn rating(&self) -> Option<f64> {
+        match self {
+            StructuredData::Product(p) => p.rating,
+            StructuredData::Recipe(r) => r.rating,
+            _ => None,
+        }
+    }
+}
@@ -69,6 +69,26 @@ impl DocumentResult {
             return Err(InvalidResult::NoDesc);
         }
 
+        // Validate OpenGraph metadata
+        if let Some(preview_image) = self.preview_image.clone() {
+            if preview_image.len() > 1000 {
+                warn!("Preview image too long for {}: {} bytes", self.cid, preview_image.len());
+                self.preview_image = None;
+            }
+        }
+        if let Some(site_name) = self.site_name.clone() {
+            if site_name.len() > 200 {
+                warn!("Site name too long for {}: {} bytes", self.cid, site_name.len());
+                self.site_name = None;
+            }
+        }
+        if let Some(published) = self.published.clone() {
+            if published.len() > 100 {
+                warn!("Publication date too long for {}: {} bytes", self.cid, published.len());
+                self.published = None;
+            }
+        }
+
         // Validate term_counts and word_count
         let positive_terms = query.positive_terms();
         if self.term_counts.len() != positive_terms.len() {
@@ -76,6 +96,27 @@ impl DocumentResult {
             return Err(InvalidResult::InvalidTermCounts);
         }
         
+        // Validate media
+        let previous_len = self.media.len();
+        self.media.truncate(20);
+        while self.media.iter().map(|media| media.src.len() + media.alt.as_ref().map(|t| t.len()).unwrap_or(0) + media.caption.as_ref().map(|t| t.len()).unwrap_or(0) + media.context.as_ref().map(|t| t.len()).unwrap_or(0)).sum::<usize>() >= 5_000 {
+            self.media.pop();
+        }
+        if previous_len != self.media.len() {
+            warn!("Removed {} media for {} to match the size limit of 5kB", previous_len - self.media.len(), self.cid);
+        }
+
+        // Validate structured_data
+        let previous_len = self.structured_data.len();
+        self.structured_data.retain(|item| item.texts().iter().all(|text| text.len() <= MAX_STRUCTURED_TEXT_LENGTH) && item.rating().map(|rating| rating.is_finite()).unwrap_or(true));
+        self.structured_data.truncate(MAX_STRUCTURED_DATA_ITEMS);
+        while self.structured_data.iter().map(|item| item.size()).sum::<usize>() > MAX_STRUCTURED_DATA_SIZE {
+            self.structured_data.pop();
+        }
+        if previous_len != self.structured_data.len() {
+            warn!("Removed {} structured data items for {} to match the size limits", previous_len - self.structured_data.len(), self.cid);
+        }
+
         // Validate common_words
         if let Some(common_words) = self.common_words {
             if !(0.0..=1.0).contains(&common_words) {
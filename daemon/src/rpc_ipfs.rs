@@ -1,12 +1,17 @@
 use crate::prelude::*;
 
-const MAX_HTML_LENGTH: usize = 15_000_000;
+pub const MAX_HTML_LENGTH: usize = 15_000_000;
+const LS_IDLE_TIMEOUT: u64 = 60;
 
 #[derive(Debug)]
 pub enum IpfsRpcError {
     Reqwest(reqwest::Error),
     Json(serde_json::Error),
     InvalidResponse(&'static str),
+    /// Kubo reported an error in the middle of a streamed response
+    Stream(String),
+    /// A streamed response stopped receiving data
+    Timeout,
 }
 
 impl From<reqwest::Error> for IpfsRpcError {
@@ -27,6 +32,8 @@ impl std::fmt::Display for IpfsRpcError {
             IpfsRpcError::Reqwest(e) => write!(f, "ReqwestError: {e}"),
             IpfsRpcError::Json(e) => write!(f, "InvalidJson: {e}"),
             IpfsRpcError::InvalidResponse(e) => write!(f, "InvalidResponse: {e}"),
+            IpfsRpcError::Stream(e) => write!(f, "StreamError: {e}"),
+            IpfsRpcError::Timeout => write!(f, "Timeout: no data received for {LS_IDLE_TIMEOUT}s"),
         }
     }
 }
@@ -45,6 +52,22 @@ pub async fn list_pinned(ipfs_rpc: &str) -> Result<Vec<String>, IpfsRpcError> {
     Ok(keys.into_iter().map(|(k,_)| k).cloned().collect())
 }
 
+/// Lists the names of recursive pins, leaving out unnamed pins
+pub async fn list_pin_names(ipfs_rpc: &str) -> Result<HashMap<String, String>, IpfsRpcError> {
+    let client = Client::new();
+    let rep = client.post(format!("{ipfs_rpc}/api/v0/pin/ls?type=recursive&names=true")).send().await?;
+    let rep = rep.text().await?;
+    let data = serde_json::from_str::<serde_json::Value>(&rep)?;
+    let keys = data
+        .get("Keys").ok_or(InvalidResponse("Keys expected on data"))?
+        .as_object().ok_or(InvalidResponse("Keys expected to be an object"))?;
+    Ok(keys
+        .iter()
+        .filter_map(|(cid, pin)| Some((cid.to_owned(), pin.get("Name")?.as_str()?.to_owned())))
+        .filter(|(_, name)| !name.is_empty())
+        .collect())
+}
+
 pub async fn get_dag(ipfs_rpc: &str, cid: &str) -> Result<serde_json::Value, IpfsRpcError> {
     let client = Client::new();
     let rep = client.post(format!("{ipfs_rpc}/api/v0/dag/get?arg={cid}")).send().await?;
@@ -65,13 +88,16 @@ pub async fn resolve(ipfs_rpc: &str, path: &str) -> Result<String, IpfsRpcError>
     Ok(cid.to_owned())
 }
 
-pub async fn ls(ipfs_rpc: &str, parent_cid: String) -> Result<Vec<(String, String, bool)>, IpfsRpcError> {
-    // TODO: streaming
-
-    let client = Client::new();
-    let rep = client.post(format!("{ipfs_rpc}/api/v0/ls?arg={parent_cid}")).send().await?;
-    let rep = rep.text().await?;
-    let rep = serde_json::from_str::<serde_json::Value>(&rep)?;
+/// Parses a line of the newline-delimited JSON returned by ls
+fn parse_ls_line(line: &[u8]) -> Result<Vec<ListedEntry>, IpfsRpcError> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Vec::new());
+    }
+    let rep = serde_json::from_slice::<serde_json::Value>(line)?;
+    if rep.get("Type").and_then(|t| t.as_str()) == Some("error") {
+        let message = rep.get("Message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+        return Err(IpfsRpcError::Stream(message.to_owned()));
+    }
 
     let objects = rep
         .get("Objects").ok_or(InvalidResponse("Objects expected on data"))?
@@ -94,19 +120,98 @@ pub async fn ls(ipfs_rpc: &str, parent_cid: String) -> Result<Vec<(String, Strin
                 .get("Type").ok_or(InvalidResponse("Type expected on link"))?
                 .as_u64().ok_or(InvalidResponse("Type expected to be a number"))?;
 
-            rep.push((child_cid.to_owned(), name.to_string(), ty == 1));
+            let size = link.get("Size").and_then(|size| size.as_u64());
+
+            // Kubo reports HAMT-sharded directories as regular directories (1)
+            rep.push((child_cid.to_owned(), name.to_string(), ty == 1, size));
         }
     }
 
     Ok(rep)
 }
 
-pub async fn fetch_document(ipfs_rpc: &str, cid: &String) -> Result<Vec<u8>, IpfsRpcError> {
+pub type LsStream = Pin<Box<dyn Stream<Item = Result<ListedEntry, IpfsRpcError>> + Send>>;
+
+/// Lists the links of a directory as Kubo streams them.
+/// HAMT-sharded directories are listed the same way as regular ones, without ever holding the whole listing in memory.
+/// The stream ends after its first error.
+pub async fn ls_stream(ipfs_rpc: &str, parent_cid: String) -> Result<LsStream, IpfsRpcError> {
+    let client = Client::new();
+    let rep = client.post(format!("{ipfs_rpc}/api/v0/ls?arg={parent_cid}&stream=true")).send().await?;
+    if rep.status() != StatusCode::OK {
+        let rep = rep.text().await?;
+        warn!("Failed to list {parent_cid}: {rep}");
+        return Err(InvalidResponse("Status code not OK"));
+    }
+
+    struct State<B> {
+        body: Pin<Box<B>>,
+        buffer: Vec<u8>,
+        pending: std::collections::VecDeque<ListedEntry>,
+        done: bool,
+    }
+
+    let state = State { body: Box::pin(rep.bytes_stream()), buffer: Vec::new(), pending: Default::default(), done: false };
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(entry) = state.pending.pop_front() {
+                return Some((Ok(entry), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            // Parse complete lines first
+            if let Some(i) = state.buffer.iter().position(|b| *b == b'\n') {
+                let line = state.buffer.drain(..=i).collect::<Vec<_>>();
+                match parse_ls_line(&line) {
+                    Ok(entries) => state.pending.extend(entries),
+                    Err(e) => {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                }
+                continue;
+            }
+
+            // Then wait for more data
+            match timeout(Duration::from_secs(LS_IDLE_TIMEOUT), state.body.next()).await {
+                Ok(Some(Ok(chunk))) => state.buffer.extend_from_slice(&chunk),
+                Ok(Some(Err(e))) => {
+                    state.done = true;
+                    return Some((Err(e.into()), state));
+                },
+                Ok(None) => {
+                    state.done = true;
+                    let line = std::mem::take(&mut state.buffer);
+                    match parse_ls_line(&line) {
+                        Ok(entries) => state.pending.extend(entries),
+                        Err(e) => return Some((Err(e), state)),
+                    }
+                },
+                Err(_) => {
+                    state.done = true;
+                    return Some((Err(IpfsRpcError::Timeout), state));
+                },
+            }
+        }
+    });
+
+    Ok(Box::pin(stream))
+}
+
+pub async fn fetch_document(ipfs_rpc: &str, cid: &str) -> Result<Vec<u8>, IpfsRpcError> {
     let client = Client::new();
     let rep = client.post(format!("{ipfs_rpc}/api/v0/cat?arg={cid}&length={MAX_HTML_LENGTH}")).send().await?;
     Ok(rep.bytes().await?.to_vec())
 }
 
+pub async fn get_block(ipfs_rpc: &str, cid: &str) -> Result<Vec<u8>, IpfsRpcError> {
+    let client = Client::new();
+    let rep = client.post(format!("{ipfs_rpc}/api/v0/block/get?arg={cid}")).send().await?;
+    Ok(rep.bytes().await?.to_vec())
+}
+
 pub async fn get_ipfs_peers(ipfs_rpc: &str) -> Result<Vec<(PeerId, Multiaddr)>, IpfsRpcError> {
     let client = Client::new();
     let rep = client.post(format!("{ipfs_rpc}/api/v0/swarm/peers")).send().await?;
@@ -0,0 +1,336 @@
+use super::*;
+use libipld::{cbor::DagCborCodec, codec::Codec, Ipld};
+use std::{fs::File, io::{BufReader, Read, Seek, SeekFrom}, sync::{Mutex, RwLock}};
+
+const CARV2_PRAGMA: [u8; 11] = [0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02];
+/// Size limit of CAR headers (in bytes), which only list roots
+const MAX_CAR_HEADER_LENGTH: u64 = 1 << 20;
+/// Size limit of CAR sections (in bytes), above the 2MiB block limit of IPFS
+const MAX_CAR_SECTION_LENGTH: u64 = 4 << 20;
+
+/// Position of a block in one of the archives
+struct BlockLocation {
+    archive: usize,
+    offset: u64,
+    length: usize,
+}
+
+#[derive(Default)]
+struct CarArchives {
+    files: Vec<Mutex<File>>,
+    /// Roots of each archive, by path
+    paths: HashMap<String, Vec<String>>,
+    roots: Vec<String>,
+    /// Blocks indexed by multihash, so that CIDv0 and CIDv1 both match
+    blocks: HashMap<Vec<u8>, BlockLocation>,
+}
+
+/// Reads content from CAR files (v1 or v2).
+/// Only block offsets are kept in memory, blocks are read from disk when needed.
+/// Archives can be added while the source is in use.
+/// Clones share the same archives.
+#[derive(Default, Clone)]
+pub struct CarSource {
+    archives: Arc<RwLock<CarArchives>>,
+}
+
+fn read_uvarint(reader: &mut impl Read) -> Result<Option<u64>, SourceError> {
+    let mut value = 0;
+    for (i, shift) in (0..64).step_by(7).enumerate() {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+            return match i {
+                0 => Ok(None),
+                _ => Err(SourceError::InvalidData("Truncated varint")),
+            };
+        }
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    Err(SourceError::InvalidData("Varint too long"))
+}
+
+/// Reads the roots and the location of every block of a CAR file
+fn index_car(path: &str) -> Result<(Vec<Cid>, Vec<(Cid, u64, usize)>), SourceError> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    // CARv2 wraps a CARv1 payload
+    let mut start = 0;
+    let mut end = file_len;
+    let mut pragma = [0; 11];
+    reader.read_exact(&mut pragma)?;
+    if pragma == CARV2_PRAGMA {
+        let mut header = [0; 40];
+        reader.read_exact(&mut header)?;
+        let data_offset = u64::from_le_bytes(header[16..24].try_into().expect("slice has the right length"));
+        let data_size = u64::from_le_bytes(header[24..32].try_into().expect("slice has the right length"));
+        start = data_offset;
+        end = data_offset.checked_add(data_size).filter(|end| *end <= file_len).ok_or(SourceError::InvalidData("CARv2 payload out of bounds"))?;
+    }
+    reader.seek(SeekFrom::Start(start))?;
+
+    // Lengths are checked against the file before anything is allocated from them
+    let fits = |data_start: u64, len: u64| data_start.checked_add(len).map(|data_end| data_end <= end).unwrap_or(false);
+
+    // Header
+    let header_len = read_uvarint(&mut reader)?.ok_or(SourceError::InvalidData("Empty CAR file"))?;
+    if header_len > MAX_CAR_HEADER_LENGTH || !fits(reader.stream_position()?, header_len) {
+        return Err(SourceError::InvalidData("CAR header too long"));
+    }
+    let mut header = vec![0; header_len as usize];
+    reader.read_exact(&mut header)?;
+    let header: Ipld = DagCborCodec.decode(&header).map_err(|_| SourceError::InvalidData("Invalid CAR header"))?;
+    let Ipld::Map(header) = header else {
+        return Err(SourceError::InvalidData("CAR header expected to be a map"));
+    };
+    if header.get("version") != Some(&Ipld::Integer(1)) {
+        return Err(SourceError::InvalidData("Unsupported CAR version"));
+    }
+    let roots = match header.get("roots") {
+        Some(Ipld::List(roots)) => roots.iter().filter_map(|root| match root {
+            Ipld::Link(cid) => Some(*cid),
+            _ => None,
+        }).collect(),
+        _ => return Err(SourceError::InvalidData("Roots expected in CAR header")),
+    };
+
+    // Sections
+    let mut blocks = Vec::new();
+    loop {
+        let section_start = reader.stream_position()?;
+        if section_start >= end {
+            break;
+        }
+        let section_len = match read_uvarint(&mut reader)? {
+            Some(0) | None => break,
+            Some(section_len) => section_len,
+        };
+        let data_start = reader.stream_position()?;
+        if section_len > MAX_CAR_SECTION_LENGTH || !fits(data_start, section_len) {
+            return Err(SourceError::InvalidData("CAR section out of bounds"));
+        }
+        let mut section = (&mut reader).take(section_len);
+        let cid = Cid::read_bytes(&mut section).map_err(|_| SourceError::InvalidData("Invalid cid in CAR section"))?;
+        let length = section.limit();
+        let offset = data_start + section_len - length;
+        reader.seek_relative(length as i64)?;
+        blocks.push((cid, offset, length as usize));
+    }
+
+    Ok((roots, blocks))
+}
+
+impl CarSource {
+    pub fn open(paths: &[String]) -> CarSource {
+        let source = CarSource::default();
+        for path in paths {
+            if let Err(e) = source.add_archive(path) {
+                error!("Failed to open CAR file {path}: {e}");
+            }
+        }
+        source
+    }
+
+    /// Indexes the blocks of a CAR file and returns its roots
+    pub fn add_archive(&self, path: &str) -> Result<Vec<String>, SourceError> {
+        if let Some(roots) = self.archives.read().ok().and_then(|archives| archives.paths.get(path).cloned()) {
+            return Ok(roots);
+        }
+
+        let start = Instant::now();
+        let (roots, blocks) = index_car(path)?;
+        let file = File::open(path)?;
+        let block_count = blocks.len();
+        let roots = roots.into_iter().map(|root| root.to_string()).collect::<Vec<_>>();
+
+        let mut archives = self.archives.write().map_err(|_| SourceError::InvalidData("Poisoned CAR archives lock"))?;
+        let archive = archives.files.len();
+        archives.files.push(Mutex::new(file));
+        for (cid, offset, length) in blocks {
+            archives.blocks.insert(cid.hash().to_bytes(), BlockLocation { archive, offset, length });
+        }
+        for root in &roots {
+            if !archives.roots.contains(root) {
+                archives.roots.push(root.to_owned());
+            }
+        }
+        archives.paths.insert(path.to_owned(), roots.clone());
+        debug!("Indexed {block_count} blocks of {path} in {}ms", start.elapsed().as_millis());
+        Ok(roots)
+    }
+
+    pub fn roots(&self) -> Vec<String> {
+        self.archives.read().map(|archives| archives.roots.clone()).unwrap_or_default()
+    }
+
+    /// Returns true if one of the archives contains the block
+    pub fn has(&self, cid: &str) -> bool {
+        let Ok(cid) = parse_cid(cid) else { return false };
+        self.archives.read().map(|archives| archives.blocks.contains_key(&cid.hash().to_bytes())).unwrap_or(false)
+    }
+}
+
+#[async_trait]
+impl BlockGetter for CarSource {
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, SourceError> {
+        let mut block;
+        {
+            let archives = self.archives.read().map_err(|_| SourceError::InvalidData("Poisoned CAR archives lock"))?;
+            let location = archives.blocks.get(&cid.hash().to_bytes()).ok_or_else(|| SourceError::NotFound(cid.to_string()))?;
+            block = vec![0; location.length];
+            let mut file = archives.files[location.archive].lock().map_err(|_| SourceError::InvalidData("Poisoned CAR file lock"))?;
+            file.seek(SeekFrom::Start(location.offset))?;
+            file.read_exact(&mut block)?;
+        }
+        verify_block(cid, &block)?;
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl ContentSource for CarSource {
+    async fn list_pinned(&self) -> Result<Vec<String>, SourceError> {
+        Ok(self.roots())
+    }
+
+    async fn ls(&self, cid: String) -> Result<EntryStream, SourceError> {
+        unixfs_ls(self.clone(), &parse_cid(&cid)?).await
+    }
+
+    async fn fetch_document(&self, cid: &str) -> Result<Vec<u8>, SourceError> {
+        unixfs_cat(self, &parse_cid(cid)?, MAX_HTML_LENGTH).await
+    }
+
+    async fn mtime(&self, cid: &str) -> Result<Option<u64>, SourceError> {
+        unixfs_mtime(self, &parse_cid(cid)?).await
+    }
+
+    async fn resolve(&self, path: &str) -> Result<String, SourceError> {
+        unixfs_resolve(self, path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+
+    fn raw_cid(block: &[u8]) -> Cid {
+        let mut bytes = vec![0x01, 0x55, 0x12, 32];
+        bytes.extend_from_slice(&Sha256::digest(block));
+        Cid::try_from(bytes.as_slice()).unwrap()
+    }
+
+    fn write_uvarint(data: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            data.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        data.push(value as u8);
+    }
+
+    /// Builds a CARv1 file containing blocks under the given cids
+    fn car_v1(roots: &[Cid], blocks: &[(Cid, &[u8])]) -> Vec<u8> {
+        let header = Ipld::Map(BTreeMap::from([
+            (String::from("roots"), Ipld::List(roots.iter().map(|root| Ipld::Link(*root)).collect())),
+            (String::from("version"), Ipld::Integer(1)),
+        ]));
+        let header = DagCborCodec.encode(&header).unwrap();
+        let mut car = Vec::new();
+        write_uvarint(&mut car, header.len() as u64);
+        car.extend(header);
+        for (cid, block) in blocks {
+            let cid = cid.to_bytes();
+            write_uvarint(&mut car, (cid.len() + block.len()) as u64);
+            car.extend(cid);
+            car.extend_from_slice(block);
+        }
+        car
+    }
+
+    /// Wraps a CARv1 payload in a CARv2 file, with some padding before the payload
+    fn car_v2(payload: &[u8]) -> Vec<u8> {
+        let data_offset = (CARV2_PRAGMA.len() + 40 + 9) as u64;
+        let mut car = CARV2_PRAGMA.to_vec();
+        car.extend([0; 16]);
+        car.extend(data_offset.to_le_bytes());
+        car.extend((payload.len() as u64).to_le_bytes());
+        car.extend(0u64.to_le_bytes());
+        car.extend([0; 9]);
+        car.extend_from_slice(payload);
+        car
+    }
+
+    fn write_car(name: &str, car: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("admarus-{}-{name}.car", std::process::id()));
+        std::fs::write(&path, car).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_car_archives() {
+        let hello = raw_cid(b"hello");
+        let world = raw_cid(b"world");
+        let tampered = raw_cid(b"tampered");
+        let payload = car_v1(&[hello], &[(hello, b"hello"), (world, b"world"), (tampered, b"tamperex")]);
+        let v1_path = write_car("v1", &payload);
+        let v2_path = write_car("v2", &car_v2(&car_v1(&[world], &[(world, b"world")])));
+
+        let source = CarSource::open(&[v1_path.clone(), v2_path.clone()]);
+        assert_eq!(source.roots(), vec![hello.to_string(), world.to_string()]);
+        assert!(source.has(&hello.to_string()));
+        assert!(!source.has(&raw_cid(b"missing").to_string()));
+        assert_eq!(source.add_archive(&v2_path).unwrap(), vec![world.to_string()]);
+
+        assert_eq!(futures::executor::block_on(source.get_block(&hello)).unwrap(), b"hello");
+        assert_eq!(futures::executor::block_on(source.get_block(&world)).unwrap(), b"world");
+        assert!(futures::executor::block_on(source.get_block(&tampered)).is_err());
+
+        let _ = std::fs::remove_file(v1_path);
+        let _ = std::fs::remove_file(v2_path);
+    }
+
+    #[test]
+    fn test_unsupported_car() {
+        let header = Ipld::Map(BTreeMap::from([
+            (String::from("roots"), Ipld::List(Vec::new())),
+            (String::from("version"), Ipld::Integer(3)),
+        ]));
+        let header = DagCborCodec.encode(&header).unwrap();
+        let mut car = Vec::new();
+        write_uvarint(&mut car, header.len() as u64);
+        car.extend(header);
+        let path = write_car("unsupported", &car);
+        assert!(matches!(index_car(&path), Err(SourceError::InvalidData("Unsupported CAR version"))));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_out_of_bounds_car() {
+        let hello = raw_cid(b"hello");
+        let mut car = car_v1(&[hello], &[(hello, b"hello")]);
+        car.truncate(car.len() - 1);
+        let truncated_path = write_car("truncated", &car);
+        assert!(matches!(index_car(&truncated_path), Err(SourceError::InvalidData("CAR section out of bounds"))));
+
+        let mut car = Vec::new();
+        write_uvarint(&mut car, u64::MAX >> 1);
+        car.extend([0; 16]);
+        let huge_header_path = write_car("huge-header", &car);
+        assert!(matches!(index_car(&huge_header_path), Err(SourceError::InvalidData("CAR header too long"))));
+
+        let mut car = car_v2(&car_v1(&[hello], &[(hello, b"hello")]));
+        car[CARV2_PRAGMA.len() + 24..CARV2_PRAGMA.len() + 32].copy_from_slice(&u64::MAX.to_le_bytes());
+        let overflow_path = write_car("overflow", &car);
+        assert!(matches!(index_car(&overflow_path), Err(SourceError::InvalidData("CARv2 payload out of bounds"))));
+
+        for path in [truncated_path, huge_header_path, overflow_path] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::*;
+
+/// Reads content from an HTTP gateway, block by block, so that everything can be verified.
+/// The gateway has to support `?format=raw` requests.
+#[derive(Clone)]
+pub struct GatewaySource {
+    gateway: String,
+    roots: Vec<String>,
+    client: Client,
+}
+
+impl GatewaySource {
+    pub fn new(gateway: String, roots: Vec<String>) -> GatewaySource {
+        GatewaySource {
+            gateway: gateway.trim_end_matches('/').to_owned(),
+            roots,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl BlockGetter for GatewaySource {
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, SourceError> {
+        let rep = self.client
+            .get(format!("{}/ipfs/{cid}?format=raw", self.gateway))
+            .header("Accept", "application/vnd.ipld.raw")
+            .send().await?;
+        if rep.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(SourceError::NotFound(cid.to_string()));
+        }
+        let block = rep.error_for_status()?.bytes().await?.to_vec();
+        verify_block(cid, &block)?;
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl ContentSource for GatewaySource {
+    async fn list_pinned(&self) -> Result<Vec<String>, SourceError> {
+        Ok(self.roots.clone())
+    }
+
+    async fn ls(&self, cid: String) -> Result<EntryStream, SourceError> {
+        unixfs_ls(self.clone(), &parse_cid(&cid)?).await
+    }
+
+    async fn fetch_document(&self, cid: &str) -> Result<Vec<u8>, SourceError> {
+        unixfs_cat(self, &parse_cid(cid)?, MAX_HTML_LENGTH).await
+    }
+
+    async fn mtime(&self, cid: &str) -> Result<Option<u64>, SourceError> {
+        unixfs_mtime(self, &parse_cid(cid)?).await
+    }
+
+    async fn resolve(&self, path: &str) -> Result<String, SourceError> {
+        unixfs_resolve(self, path).await
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+/// Reads content through the Kubo RPC
+pub struct KuboSource {
+    ipfs_rpc: String,
+}
+
+impl KuboSource {
+    pub fn new(ipfs_rpc: String) -> KuboSource {
+        KuboSource { ipfs_rpc }
+    }
+}
+
+#[async_trait]
+impl BlockGetter for KuboSource {
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, SourceError> {
+        Ok(crate::rpc_ipfs::get_block(&self.ipfs_rpc, &cid.to_string()).await?)
+    }
+}
+
+#[async_trait]
+impl ContentSource for KuboSource {
+    async fn list_pinned(&self) -> Result<Vec<String>, SourceError> {
+        Ok(crate::rpc_ipfs::list_pinned(&self.ipfs_rpc).await?)
+    }
+
+    async fn pin_names(&self) -> Result<HashMap<String, String>, SourceError> {
+        Ok(crate::rpc_ipfs::list_pin_names(&self.ipfs_rpc).await?)
+    }
+
+    async fn ls(&self, cid: String) -> Result<EntryStream, SourceError> {
+        let entries = crate::rpc_ipfs::ls_stream(&self.ipfs_rpc, cid).await?;
+        Ok(Box::pin(entries.map(|entry| entry.map_err(SourceError::from))))
+    }
+
+    async fn fetch_document(&self, cid: &str) -> Result<Vec<u8>, SourceError> {
+        Ok(crate::rpc_ipfs::fetch_document(&self.ipfs_rpc, cid).await?)
+    }
+
+    async fn mtime(&self, cid: &str) -> Result<Option<u64>, SourceError> {
+        unixfs_mtime(self, &parse_cid(cid)?).await
+    }
+
+    async fn resolve(&self, path: &str) -> Result<String, SourceError> {
+        Ok(crate::rpc_ipfs::resolve(&self.ipfs_rpc, path).await?)
+    }
+}
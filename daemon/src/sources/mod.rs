@@ -0,0 +1,169 @@
+use crate::prelude::*;
+
+mod unixfs;
+mod kubo;
+mod gateway;
+mod car;
+pub(self) use unixfs::*;
+pub use kubo::*;
+pub use gateway::*;
+pub use car::*;
+
+#[derive(Debug)]
+pub enum SourceError {
+    Rpc(IpfsRpcError),
+    Http(reqwest::Error),
+    Io(std::io::Error),
+    InvalidData(&'static str),
+    NotFound(String),
+}
+
+impl From<IpfsRpcError> for SourceError {
+    fn from(e: IpfsRpcError) -> Self {
+        SourceError::Rpc(e)
+    }
+}
+
+impl From<reqwest::Error> for SourceError {
+    fn from(e: reqwest::Error) -> Self {
+        SourceError::Http(e)
+    }
+}
+
+impl From<std::io::Error> for SourceError {
+    fn from(e: std::io::Error) -> Self {
+        SourceError::Io(e)
+    }
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceError::Rpc(e) => write!(f, "{e}"),
+            SourceError::Http(e) => write!(f, "HttpError: {e}"),
+            SourceError::Io(e) => write!(f, "IoError: {e}"),
+            SourceError::InvalidData(e) => write!(f, "InvalidData: {e}"),
+            SourceError::NotFound(e) => write!(f, "NotFound: {e}"),
+        }
+    }
+}
+
+/// Link of a directory, as (cid, name, is_folder, size).
+/// The size is that of the linked content in bytes, if the source knows it.
+pub type ListedEntry = (String, String, bool, Option<u64>);
+
+/// Links of a directory
+pub type EntryStream = Pin<Box<dyn Stream<Item = Result<ListedEntry, SourceError>> + Send>>;
+
+/// Somewhere IPFS content can be read from
+#[async_trait]
+pub trait ContentSource: Send + Sync {
+    /// Lists the roots that should be indexed
+    async fn list_pinned(&self) -> Result<Vec<String>, SourceError>;
+
+    /// Lists the names of pinned roots, for sources where pins can be named
+    async fn pin_names(&self) -> Result<HashMap<String, String>, SourceError> {
+        Ok(HashMap::new())
+    }
+
+    /// Lists the links of a directory.
+    /// Files have no links.
+    async fn ls(&self, cid: String) -> Result<EntryStream, SourceError>;
+
+    /// Reads the content of a file, truncated to [MAX_HTML_LENGTH] bytes
+    async fn fetch_document(&self, cid: &str) -> Result<Vec<u8>, SourceError>;
+
+    /// Reads the UnixFS modification time of a file, in seconds since the Unix epoch.
+    /// Most content doesn't record it.
+    async fn mtime(&self, _cid: &str) -> Result<Option<u64>, SourceError> {
+        Ok(None)
+    }
+
+    /// Resolves an IPFS path such as `/ipfs/cid/dir/file` to a cid
+    async fn resolve(&self, path: &str) -> Result<String, SourceError>;
+}
+
+/// Adds the content of CAR files to another source
+pub struct WithCarFiles {
+    main: Arc<dyn ContentSource>,
+    cars: Arc<CarSource>,
+}
+
+#[async_trait]
+impl ContentSource for WithCarFiles {
+    async fn list_pinned(&self) -> Result<Vec<String>, SourceError> {
+        let mut pinned = self.main.list_pinned().await?;
+        pinned.extend(self.cars.roots());
+        Ok(pinned)
+    }
+
+    async fn pin_names(&self) -> Result<HashMap<String, String>, SourceError> {
+        self.main.pin_names().await
+    }
+
+    async fn ls(&self, cid: String) -> Result<EntryStream, SourceError> {
+        match self.cars.has(&cid) {
+            true => self.cars.ls(cid).await,
+            false => self.main.ls(cid).await,
+        }
+    }
+
+    async fn fetch_document(&self, cid: &str) -> Result<Vec<u8>, SourceError> {
+        match self.cars.has(cid) {
+            true => self.cars.fetch_document(cid).await,
+            false => self.main.fetch_document(cid).await,
+        }
+    }
+
+    async fn mtime(&self, cid: &str) -> Result<Option<u64>, SourceError> {
+        match self.cars.has(cid) {
+            true => self.cars.mtime(cid).await,
+            false => self.main.mtime(cid).await,
+        }
+    }
+
+    async fn resolve(&self, path: &str) -> Result<String, SourceError> {
+        let root = path.trim_start_matches("/ipfs/").trim_start_matches('/').split('/').next().unwrap_or_default();
+        match self.cars.has(root) {
+            true => self.cars.resolve(path).await,
+            false => self.main.resolve(path).await,
+        }
+    }
+}
+
+/// Lists the CAR files given in the config and those previously uploaded
+fn car_paths(config: &Args) -> Vec<String> {
+    let mut paths = config.car_files.clone();
+    if let Ok(entries) = std::fs::read_dir(&config.car_dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().map(|ext| ext == "car").unwrap_or(false) {
+                paths.push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+    paths
+}
+
+/// Opens the source selected in the config.
+/// CAR files are available with every source, and the returned [CarSource] accepts new archives at runtime.
+pub fn open_source(config: &Args) -> (Arc<dyn ContentSource>, Arc<CarSource>) {
+    let cars = Arc::new(CarSource::open(&car_paths(config)));
+    let main: Arc<dyn ContentSource> = match config.source {
+        SourceKind::Kubo => Arc::new(KuboSource::new(config.ipfs_rpc.clone())),
+        SourceKind::Gateway => {
+            if config.source_roots.is_empty() {
+                warn!("No source_roots were given, so there is nothing to index from the gateway");
+            }
+            Arc::new(GatewaySource::new(config.gateway_url.clone(), config.source_roots.clone()))
+        },
+        SourceKind::Car => {
+            if cars.roots().is_empty() {
+                warn!("No CAR files were loaded, so there is nothing to index until some are uploaded");
+            }
+            return (Arc::clone(&cars) as Arc<dyn ContentSource>, cars);
+        },
+    };
+    let source = Arc::new(WithCarFiles { main, cars: Arc::clone(&cars) });
+    (source, cars)
+}
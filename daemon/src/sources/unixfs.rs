@@ -0,0 +1,371 @@
+use super::*;
+use libipld::{codec::Codec, pb::DagPbCodec, Ipld};
+use sha2::{Digest, Sha256};
+
+const DAG_PB: u64 = 0x70;
+const RAW: u64 = 0x55;
+const SHA2_256: u64 = 0x12;
+/// Number of children whose type is read at the same time when listing a directory
+const LS_PARALLELISM: usize = 8;
+
+/// Gives access to raw blocks, from which UnixFS files and directories are read
+#[async_trait]
+pub trait BlockGetter: Send + Sync {
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, SourceError>;
+}
+
+/// Checks that a block matches its cid.
+/// Only sha2-256 is checked as it is the hash used by the overwhelming majority of content.
+pub fn verify_block(cid: &Cid, block: &[u8]) -> Result<(), SourceError> {
+    if cid.hash().code() == SHA2_256 && Sha256::digest(block).as_slice() != cid.hash().digest() {
+        return Err(SourceError::InvalidData("Block doesn't match its cid"));
+    }
+    Ok(())
+}
+
+pub fn parse_cid(cid: &str) -> Result<Cid, SourceError> {
+    Cid::try_from(cid).map_err(|_| SourceError::InvalidData("Invalid cid"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnixFsType {
+    Raw,
+    Directory,
+    File,
+    Metadata,
+    Symlink,
+    HamtShard,
+}
+
+struct UnixFsData {
+    ty: UnixFsType,
+    data: Vec<u8>,
+    fanout: Option<u64>,
+    /// Modification time, in seconds since the Unix epoch
+    mtime: Option<u64>,
+}
+
+struct PbNode {
+    /// Links as (cid, name, cumulative size)
+    links: Vec<(Cid, String, Option<u64>)>,
+    unixfs: Option<UnixFsData>,
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Decodes the seconds of a UnixTime message, ignoring times before the Unix epoch
+fn decode_unixtime(data: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    while pos < data.len() {
+        let key = read_varint(data, &mut pos)?;
+        match (key >> 3, key & 7) {
+            (1, 0) => return Some(read_varint(data, &mut pos)?).filter(|seconds| (*seconds as i64) >= 0),
+            (_, 0) => { read_varint(data, &mut pos)?; },
+            (_, 5) => pos += 4,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Decodes the protobuf message stored in the Data field of UnixFS nodes
+fn decode_unixfs_data(data: &[u8]) -> Option<UnixFsData> {
+    let mut pos = 0;
+    let mut ty = None;
+    let mut content = Vec::new();
+    let mut fanout = None;
+    let mut mtime = None;
+    while pos < data.len() {
+        let key = read_varint(data, &mut pos)?;
+        match (key >> 3, key & 7) {
+            (1, 0) => ty = Some(read_varint(data, &mut pos)?),
+            (2, 2) => {
+                let len = read_varint(data, &mut pos)? as usize;
+                content = data.get(pos..pos.checked_add(len)?)?.to_vec();
+                pos += len;
+            },
+            (6, 0) => fanout = Some(read_varint(data, &mut pos)?),
+            (8, 2) => {
+                let len = read_varint(data, &mut pos)? as usize;
+                mtime = decode_unixtime(data.get(pos..pos.checked_add(len)?)?);
+                pos += len;
+            },
+            (_, 0) => { read_varint(data, &mut pos)?; },
+            (_, 1) => pos += 8,
+            (_, 2) => {
+                let len = read_varint(data, &mut pos)? as usize;
+                pos = pos.checked_add(len)?;
+            },
+            (_, 5) => pos += 4,
+            _ => return None,
+        }
+    }
+    let ty = match ty? {
+        0 => UnixFsType::Raw,
+        1 => UnixFsType::Directory,
+        2 => UnixFsType::File,
+        3 => UnixFsType::Metadata,
+        4 => UnixFsType::Symlink,
+        5 => UnixFsType::HamtShard,
+        _ => return None,
+    };
+    Some(UnixFsData { ty, data: content, fanout, mtime })
+}
+
+fn decode_node(block: &[u8]) -> Result<PbNode, SourceError> {
+    let ipld: Ipld = DagPbCodec.decode(block).map_err(|_| SourceError::InvalidData("Invalid dag-pb block"))?;
+    let Ipld::Map(map) = ipld else {
+        return Err(SourceError::InvalidData("dag-pb block expected to be a map"));
+    };
+
+    let mut links = Vec::new();
+    if let Some(Ipld::List(ipld_links)) = map.get("Links") {
+        for link in ipld_links {
+            let Ipld::Map(link) = link else { continue };
+            let Some(Ipld::Link(cid)) = link.get("Hash") else {
+                return Err(SourceError::InvalidData("Hash expected on link"));
+            };
+            let name = match link.get("Name") {
+                Some(Ipld::String(name)) => name.to_owned(),
+                _ => String::new(),
+            };
+            let size = match link.get("Tsize") {
+                Some(Ipld::Integer(size)) => u64::try_from(*size).ok(),
+                _ => None,
+            };
+            links.push((*cid, name, size));
+        }
+    }
+    let unixfs = match map.get("Data") {
+        Some(Ipld::Bytes(data)) => decode_unixfs_data(data),
+        _ => None,
+    };
+
+    Ok(PbNode { links, unixfs })
+}
+
+/// Lists the links of a directory, flattening HAMT shards
+async fn list_links(store: &impl BlockGetter, cid: &Cid) -> Result<Vec<(Cid, String, Option<u64>)>, SourceError> {
+    if cid.codec() != DAG_PB {
+        return Ok(Vec::new());
+    }
+
+    let mut links = Vec::new();
+    let mut to_explore = vec![(*cid, true)];
+    while let Some((cid, is_root)) = to_explore.pop() {
+        let node = decode_node(&store.get_block(&cid).await?)?;
+        let Some(unixfs) = node.unixfs else { continue };
+        match unixfs.ty {
+            UnixFsType::Directory if is_root => links.extend(node.links),
+            UnixFsType::HamtShard => {
+                // Link names are prefixed with the hex-encoded bucket index of the entry
+                // Links that only have this prefix point to sub-shards
+                let fanout = unixfs.fanout.unwrap_or(256).max(2);
+                let prefix_len = format!("{:X}", fanout - 1).len();
+                for (child_cid, name, size) in node.links {
+                    match name.get(prefix_len..) {
+                        Some("") => to_explore.push((child_cid, false)),
+                        Some(name) => links.push((child_cid, name.to_owned(), size)),
+                        None => return Err(SourceError::InvalidData("HAMT link name too short")),
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    Ok(links)
+}
+
+async fn is_directory(store: &impl BlockGetter, cid: &Cid) -> Result<bool, SourceError> {
+    if cid.codec() != DAG_PB {
+        return Ok(false);
+    }
+    let node = decode_node(&store.get_block(cid).await?)?;
+    Ok(matches!(node.unixfs.map(|u| u.ty), Some(UnixFsType::Directory) | Some(UnixFsType::HamtShard)))
+}
+
+/// Lists the entries of a UnixFS directory.
+/// Telling folders from files takes the block of each dag-pb child, so these are fetched a few at a time while the stream is read,
+/// and the first entries don't wait for the others.
+/// Sizes are the cumulative sizes of links, which slightly exceed the size of file contents.
+pub async fn unixfs_ls<S: BlockGetter + Clone + 'static>(store: S, cid: &Cid) -> Result<EntryStream, SourceError> {
+    let links = list_links(&store, cid).await?;
+    let entries = futures::stream::iter(links)
+        .map(move |(child_cid, name, size)| {
+            let store = store.clone();
+            async move {
+                let is_folder = is_directory(&store, &child_cid).await?;
+                Ok((child_cid.to_string(), name, is_folder, size))
+            }
+        })
+        .buffered(LS_PARALLELISM);
+    Ok(Box::pin(entries))
+}
+
+/// Reads the content of a UnixFS file, up to max_length bytes
+pub async fn unixfs_cat(store: &impl BlockGetter, cid: &Cid, max_length: usize) -> Result<Vec<u8>, SourceError> {
+    let mut content = Vec::new();
+    let mut to_read = vec![*cid];
+    while let Some(cid) = to_read.pop() {
+        if content.len() >= max_length {
+            break;
+        }
+        let block = store.get_block(&cid).await?;
+        match cid.codec() {
+            RAW => content.extend_from_slice(&block),
+            DAG_PB => {
+                let node = decode_node(&block)?;
+                let Some(unixfs) = node.unixfs else {
+                    return Err(SourceError::InvalidData("UnixFS data expected on dag-pb node"));
+                };
+                if !matches!(unixfs.ty, UnixFsType::File | UnixFsType::Raw) {
+                    return Err(SourceError::InvalidData("Not a file"));
+                }
+                content.extend_from_slice(&unixfs.data);
+                to_read.extend(node.links.into_iter().rev().map(|(cid, _, _)| cid));
+            },
+            _ => return Err(SourceError::InvalidData("Unsupported codec")),
+        }
+    }
+    content.truncate(max_length);
+    Ok(content)
+}
+
+/// Reads the modification time of a UnixFS file or directory, if it was recorded
+pub async fn unixfs_mtime(store: &impl BlockGetter, cid: &Cid) -> Result<Option<u64>, SourceError> {
+    if cid.codec() != DAG_PB {
+        return Ok(None);
+    }
+    let node = decode_node(&store.get_block(cid).await?)?;
+    Ok(node.unixfs.and_then(|unixfs| unixfs.mtime))
+}
+
+/// Resolves a path such as `/ipfs/cid/dir/file` by going through directories
+pub async fn unixfs_resolve(store: &impl BlockGetter, path: &str) -> Result<String, SourceError> {
+    let path = path.trim_start_matches("/ipfs/").trim_start_matches('/');
+    let mut parts = path.split('/').filter(|part| !part.is_empty());
+    let root = parts.next().ok_or(SourceError::InvalidData("Empty path"))?;
+    let mut cid = parse_cid(root)?;
+    for part in parts {
+        let links = list_links(store, &cid).await?;
+        cid = match links.into_iter().find(|(_, name, _)| name == part) {
+            Some((child_cid, _, _)) => child_cid,
+            None => return Err(SourceError::NotFound(format!("{part} in {cid}"))),
+        };
+    }
+    Ok(cid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Default, Clone)]
+    struct MemoryStore {
+        blocks: HashMap<Cid, Vec<u8>>,
+    }
+
+    #[async_trait]
+    impl BlockGetter for MemoryStore {
+        async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, SourceError> {
+            self.blocks.get(cid).cloned().ok_or_else(|| SourceError::NotFound(cid.to_string()))
+        }
+    }
+
+    impl MemoryStore {
+        fn put(&mut self, codec: u64, block: Vec<u8>) -> Cid {
+            let mut bytes = vec![0x01, codec as u8, SHA2_256 as u8, 32];
+            bytes.extend_from_slice(&Sha256::digest(&block));
+            let cid = Cid::try_from(bytes.as_slice()).unwrap();
+            self.blocks.insert(cid, block);
+            cid
+        }
+
+        fn put_node(&mut self, unixfs_data: Vec<u8>, links: &[(Cid, &str)]) -> Cid {
+            let links = links.iter().map(|(cid, name)| Ipld::Map(BTreeMap::from([
+                (String::from("Hash"), Ipld::Link(*cid)),
+                (String::from("Name"), Ipld::String(name.to_string())),
+                (String::from("Tsize"), Ipld::Integer(5)),
+            ]))).collect();
+            let node = Ipld::Map(BTreeMap::from([
+                (String::from("Data"), Ipld::Bytes(unixfs_data)),
+                (String::from("Links"), Ipld::List(links)),
+            ]));
+            self.put(DAG_PB, DagPbCodec.encode(&node).unwrap())
+        }
+    }
+
+    /// Encodes the Data field of a UnixFS node, with a fanout of 256 for HAMT shards
+    fn unixfs_data(ty: u8, mtime: Option<u8>) -> Vec<u8> {
+        let mut data = vec![0x08, ty];
+        if ty == 5 {
+            data.extend([0x30, 0x80, 0x02]);
+        }
+        if let Some(mtime) = mtime {
+            data.extend([0x42, 0x02, 0x08, mtime]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_decode_unixfs_data() {
+        let data = decode_unixfs_data(&[0x08, 0x02, 0x12, 0x02, b'h', b'i', 0x42, 0x02, 0x08, 0x7f]).unwrap();
+        assert_eq!(data.ty, UnixFsType::File);
+        assert_eq!(data.data, b"hi");
+        assert_eq!(data.mtime, Some(127));
+
+        let data = decode_unixfs_data(&[0x08, 0x05, 0x30, 0x80, 0x02]).unwrap();
+        assert_eq!(data.ty, UnixFsType::HamtShard);
+        assert_eq!(data.fanout, Some(256));
+
+        assert!(decode_unixfs_data(&[0x08, 0x09]).is_none());
+        assert!(decode_unixfs_data(&[0x12, 0x05, b'h']).is_none());
+    }
+
+    #[test]
+    fn test_hamt_directory() {
+        let mut store = MemoryStore::default();
+        let a = store.put(RAW, b"hello".to_vec());
+        let b = store.put(RAW, b"world".to_vec());
+        let sub_shard = store.put_node(unixfs_data(5, None), &[(b, "1Fb.txt")]);
+        let root = store.put_node(unixfs_data(5, Some(42)), &[(sub_shard, "00"), (a, "A3a.txt")]);
+
+        let entries = futures::executor::block_on(unixfs_ls(store.clone(), &root)).unwrap();
+        let mut entries = futures::executor::block_on(entries.collect::<Vec<_>>()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        entries.sort_by(|entry1, entry2| entry1.1.cmp(&entry2.1));
+        assert_eq!(entries, vec![
+            (a.to_string(), String::from("a.txt"), false, Some(5)),
+            (b.to_string(), String::from("b.txt"), false, Some(5)),
+        ]);
+
+        let resolved = futures::executor::block_on(unixfs_resolve(&store, &format!("/ipfs/{root}/b.txt"))).unwrap();
+        assert_eq!(resolved, b.to_string());
+        assert_eq!(futures::executor::block_on(unixfs_cat(&store, &b, 3)).unwrap(), b"wor");
+        assert_eq!(futures::executor::block_on(unixfs_mtime(&store, &root)).unwrap(), Some(42));
+
+        let file = store.put_node(unixfs_data(2, None), &[]);
+        let bad_shard = store.put_node(unixfs_data(5, None), &[(file, "A")]);
+        assert!(futures::executor::block_on(unixfs_ls(store.clone(), &bad_shard)).is_err());
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let mut store = MemoryStore::default();
+        let cid = store.put(RAW, b"hello".to_vec());
+        assert!(verify_block(&cid, b"hello").is_ok());
+        assert!(verify_block(&cid, b"hellp").is_err());
+    }
+}
@@ -0,0 +1,48 @@
+//! Reduces words to their stem so that a query for "running" also matches documents about "run".
+//! Stems are stored in the index next to words, under keys prefixed by [STEM_PREFIX].
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::sync::OnceLock;
+
+/// Prefix of index keys holding stems.
+/// The tokenizer never produces words starting with it, so stems can't collide with words.
+pub const STEM_PREFIX: char = '~';
+
+const ALGORITHMS: &[(&str, Algorithm)] = &[
+    ("ar", Algorithm::Arabic),
+    ("da", Algorithm::Danish),
+    ("de", Algorithm::German),
+    ("el", Algorithm::Greek),
+    ("en", Algorithm::English),
+    ("es", Algorithm::Spanish),
+    ("fr", Algorithm::French),
+    ("hu", Algorithm::Hungarian),
+    ("it", Algorithm::Italian),
+    ("nl", Algorithm::Dutch),
+    ("no", Algorithm::Norwegian),
+    ("pt", Algorithm::Portuguese),
+    ("ro", Algorithm::Romanian),
+    ("ru", Algorithm::Russian),
+    ("sv", Algorithm::Swedish),
+    ("ta", Algorithm::Tamil),
+    ("tr", Algorithm::Turkish),
+];
+
+static STEMMERS: OnceLock<Vec<(&'static str, Stemmer)>> = OnceLock::new();
+
+/// Returns the stemmer for a language, if that language is supported.
+/// Stemmers are only created once.
+pub fn stemmer(lang: &str) -> Option<&'static Stemmer> {
+    let stemmers = STEMMERS.get_or_init(|| ALGORITHMS.iter().map(|(code, algorithm)| (*code, Stemmer::create(*algorithm))).collect());
+    stemmers.iter().find(|(code, _)| *code == lang).map(|(_, stemmer)| stemmer)
+}
+
+pub fn stem_key(stemmer: &Stemmer, word: &str) -> String {
+    format!("{STEM_PREFIX}{}", stemmer.stem(word))
+}
+
+/// Returns the index key under which documents containing a word with the same stem can be found.
+/// Words are only stemmed when the language of the query is known and supported.
+pub fn query_stem_key(word: &str, lang: Option<&str>) -> Option<String> {
+    Some(stem_key(stemmer(lang?)?, word))
+}
@@ -0,0 +1,92 @@
+//! Splits text into words the same way everywhere: when indexing, generating results and ranking them.
+//! This file is shared between the daemon and the webui.
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words shorter than this (in characters) are ignored, except in scripts written without spaces
+pub const MIN_WORD_LENGTH: usize = 3;
+
+/// A word found in a text, with its position (in bytes) in that text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub word: String,
+}
+
+/// Returns true for characters of scripts that don't separate words with spaces.
+/// Text in these scripts is indexed as overlapping pairs of characters.
+fn is_unspaced(c: char) -> bool {
+    matches!(c as u32,
+        0x0E00..=0x0EFF // Thai, Lao
+        | 0x1000..=0x109F // Myanmar
+        | 0x1780..=0x17FF // Khmer
+        | 0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF // CJK Extensions B to F
+    )
+}
+
+/// Returns true for apostrophes, which separate elided words such as "l'été"
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Folds case and removes diacritics, so that "Été" and "ete" are the same word.
+/// Only the generic combining diacritics are removed, as marks of other scripts are often part of letters.
+pub fn normalize_word(word: &str) -> String {
+    word.nfkd().filter(|c| !('\u{0300}'..='\u{036F}').contains(c)).nfc().flat_map(char::to_lowercase).collect()
+}
+
+fn flush_run(run: &mut Vec<(usize, char)>, tokens: &mut Vec<Token>) {
+    match run.len() {
+        0 => (),
+        1 => {
+            let (start, c) = run[0];
+            tokens.push(Token { start, end: start + c.len_utf8(), word: normalize_word(&c.to_string()) });
+        },
+        _ => {
+            for pair in run.windows(2) {
+                let (start, first) = pair[0];
+                let (second_start, second) = pair[1];
+                let word = normalize_word(&[first, second].iter().collect::<String>());
+                tokens.push(Token { start, end: second_start + second.len_utf8(), word });
+            }
+        },
+    }
+    run.clear();
+}
+
+/// Splits text into normalized words, following Unicode word segmentation
+pub fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut run = Vec::new();
+    for (start, segment) in text.split_word_bound_indices() {
+        if segment.chars().any(is_unspaced) {
+            run.extend(segment.char_indices().filter(|(_, c)| is_unspaced(*c)).map(|(i, c)| (start + i, c)));
+            continue;
+        }
+        flush_run(&mut run, &mut tokens);
+        let mut part_start = start;
+        for part in segment.split(is_apostrophe) {
+            let part_end = part_start + part.len();
+            if part.chars().any(char::is_alphanumeric) {
+                let word = normalize_word(part);
+                if word.chars().count() >= MIN_WORD_LENGTH {
+                    tokens.push(Token { start: part_start, end: part_end, word });
+                }
+            }
+            part_start = part_end + segment[part_end - start..].chars().next().map(char::len_utf8).unwrap_or(0);
+        }
+    }
+    flush_run(&mut run, &mut tokens);
+    tokens
+}
+
+/// Splits text into normalized words, following Unicode word segmentation
+pub fn tokenize(text: &str) -> Vec<String> {
+    tokens(text).into_iter().map(|token| token.word).collect()
+}
@@ -158,8 +158,11 @@ pub async fn fetch_results(rpc_addr: &str, id: u64) -> Result<Vec<(DocumentResul
     get(format!("{rpc_addr}/results?id={id}")).await
 }
 
-pub async fn get_result(rpc_addr: &str, id: u64, cid: &str) -> Result<Option<DocumentResult>, ApiError> {
-    get(format!("{rpc_addr}/result?id={id}&cid={cid}")).await
+pub async fn get_result(rpc_addr: &str, id: u64, cid: &str, path: Option<&[String]>) -> Result<Option<DocumentResult>, ApiError> {
+    match path {
+        Some(path) => get(format!("{rpc_addr}/result?id={id}&cid={cid}&path={}", url_encode(&path.join("/")))).await,
+        None => get(format!("{rpc_addr}/result?id={id}&cid={cid}")).await,
+    }
 }
 
 pub async fn get_api_version(rpc_addr: &str) -> Result<u64, ApiError> {
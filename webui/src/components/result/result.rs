@@ -68,6 +68,12 @@ impl Component for GroupedResultsComp {
         let title_first = title_iter.next().unwrap_or_default();
         let desc_first = desc_iter.next().unwrap_or_default();
         let addr_first = ctx.props().results.first().unwrap().0.format_best_addr();
+        let preview_first = ctx.props().results.first().unwrap().0.format_preview_image(conn_status);
+        let has_preview_first = preview_first.is_some();
+        let preview_first = preview_first.unwrap_or_default();
+        let structured_first = ctx.props().results.first().unwrap().0.format_structured_data();
+        let has_structured_first = structured_first.is_some();
+        let structured_first = structured_first.unwrap_or_default();
 
         // Favicons
         let icon_sizes_iter = favicon_iter().map(|desc| desc.sizes.to_owned());
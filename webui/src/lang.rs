@@ -1,13 +1,67 @@
 use word_lists::*;
+use crate::prelude::*;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Lang {
+    Czech,
+    Danish,
+    Dutch,
     English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Polish,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Turkish,
 }
 
 impl Lang {
-    pub fn common_words(&self) -> &[&str] {
+    /// Returns the ISO 639-1 code of the language
+    pub fn code(&self) -> &'static str {
         match self {
-            Lang::English => WORDS_EN,
+            Lang::Czech => "cs",
+            Lang::Danish => "da",
+            Lang::Dutch => "nl",
+            Lang::English => "en",
+            Lang::Finnish => "fi",
+            Lang::French => "fr",
+            Lang::German => "de",
+            Lang::Greek => "el",
+            Lang::Hungarian => "hu",
+            Lang::Italian => "it",
+            Lang::Norwegian => "no",
+            Lang::Polish => "pl",
+            Lang::Portuguese => "pt",
+            Lang::Romanian => "ro",
+            Lang::Russian => "ru",
+            Lang::Spanish => "es",
+            Lang::Swedish => "sv",
+            Lang::Turkish => "tr",
         }
     }
+
+    pub fn from_code(code: &str) -> Option<Lang> {
+        [
+            Lang::Czech, Lang::Danish, Lang::Dutch, Lang::English, Lang::Finnish, Lang::French,
+            Lang::German, Lang::Greek, Lang::Hungarian, Lang::Italian, Lang::Norwegian, Lang::Polish,
+            Lang::Portuguese, Lang::Romanian, Lang::Russian, Lang::Spanish, Lang::Swedish, Lang::Turkish,
+        ].into_iter().find(|lang| lang.code() == code)
+    }
+
+    /// Returns the language requested by a `lang=` filter, defaulting to English
+    pub fn from_query(query: &Query) -> Lang {
+        query.lang_filter().and_then(|value| Lang::from_code(value)).unwrap_or(Lang::English)
+    }
+
+    pub fn common_words(&self) -> &[&str] {
+        common_words(self.code()).unwrap_or_default()
+    }
 }
@@ -6,6 +6,7 @@ mod api_bodies;
 mod api;
 mod lucky;
 mod lang;
+mod tokenizer;
 
 #[path = "pages/search/search.rs"]
 mod search;
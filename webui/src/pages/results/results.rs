@@ -194,6 +194,37 @@ impl Component for ResultsPage {
         let conn_status = Rc::clone(&ctx.props().conn_status);
         let onchange_conn_status = ctx.props().onchange_conn_status.clone();
 
+        // Media grid
+        let media_kind = match self.document_type {
+            DocumentType::Images => Some(MediaKind::Image),
+            DocumentType::Videos => Some(MediaKind::Video),
+            _ => None,
+        };
+        let media_conn_status = conn_status.as_ref();
+        let media_components = match media_kind {
+            Some(media_kind) => results.iter().flatten().flat_map(|(result, _)| {
+                let href = result.format_best_href(media_conn_status);
+                let title = result.format_result_title();
+                result.media.iter().filter(move |media| media.kind == media_kind).filter_map(move |media| {
+                    let src = media.format_src(result.paths.first(), media_conn_status)?;
+                    let label = media.caption.clone().or_else(|| media.alt.clone()).unwrap_or_else(|| title.clone());
+                    let element = match media.kind {
+                        MediaKind::Image => html! { <img src={src} alt={media.alt.clone().unwrap_or_default()} loading="lazy" /> },
+                        MediaKind::Video => html! { <video src={src} preload="metadata" muted={true} /> },
+                    };
+                    Some(html! {
+                        <a class="media-result" href={href.clone()} title={media.context.clone().unwrap_or_else(|| title.clone())}>
+                            {element}
+                            <span>{label}</span>
+                        </a>
+                    })
+                }).collect::<Vec<_>>()
+            }).collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        let no_media = media_components.is_empty();
+        let media_components: Html = media_components.into_iter().collect();
+
         let result_components: Html = match query {
             Some(query) => {
                 let query = Rc::new(query.to_owned());
@@ -25,6 +25,63 @@ fn format_path_for_gateway(mut path: &[String], conn_status: &ConnectionStatus)
     }
 }
 
+/// Resolves the source of a media against the path of the document it was found in.
+/// Relative sources become `ipfs://cid/path` or `ipns://domain/path`.
+/// Returns None for sources that can't be displayed, such as data URIs.
+pub fn resolve_media_src(src: &str, doc_path: Option<&Vec<String>>) -> Option<String> {
+    if let Some((scheme, _)) = src.split_once("://") {
+        return match ["http", "https", "ipfs", "ipns"].contains(&scheme.to_lowercase().as_str()) {
+            true => Some(src.to_owned()),
+            false => None,
+        };
+    }
+    if let Some(src) = src.strip_prefix("//") {
+        return Some(format!("https://{src}"));
+    }
+    if src.split('/').next().unwrap_or_default().contains(':') {
+        // Other schemes such as data: or javascript:
+        return None;
+    }
+
+    let doc_path = doc_path?;
+    let root = doc_path.first()?;
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+    let mut path = match src.starts_with('/') {
+        true => Vec::new(),
+        false => doc_path[1..doc_path.len().saturating_sub(1).max(1)].to_vec(),
+    };
+    for segment in src.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => { path.pop()?; },
+            segment => path.push(segment.to_owned()),
+        }
+    }
+    let scheme = match root.contains('.') {
+        true => "ipns",
+        false => "ipfs",
+    };
+    Some(format!("{scheme}://{root}/{}", path.join("/")))
+}
+
+/// Turns an address resolved by [resolve_media_src] into a link to the gateway
+fn format_media_addr(addr: String, conn_status: &ConnectionStatus) -> String {
+    let ipfs_path = addr.strip_prefix("ipfs://").or_else(|| addr.strip_prefix("ipns://"));
+    match ipfs_path {
+        Some(ipfs_path) => {
+            let path = ipfs_path.split('/').map(|p| p.to_string()).collect::<Vec<_>>();
+            format_path_for_gateway(&path, conn_status).unwrap_or(addr)
+        },
+        None => addr,
+    }
+}
+
+impl MediaDescriptor {
+    pub fn format_src(&self, doc_path: Option<&Vec<String>>, conn_status: &ConnectionStatus) -> Option<String> {
+        resolve_media_src(&self.src, doc_path).map(|addr| format_media_addr(addr, conn_status))
+    }
+}
+
 impl FaviconDescriptor {
     pub fn format_srcset(&self, doc_path: Option<&Vec<String>>, conn_status: &ConnectionStatus) -> Option<String> {
         if self.href.starts_with("http://") || self.href.starts_with("https://") || self.href.starts_with("ipfs://") || self.href.starts_with("ipns://") {
@@ -95,6 +152,26 @@ impl FaviconDescriptor {
     }
 }
 
+impl StructuredData {
+    /// Formats the properties worth showing under a result, such as the price of a product
+    pub fn format_summary(&self) -> String {
+        let rating = self.rating().map(|rating| format!("★ {rating:.1}"));
+        let price = |price: &Option<String>, currency: &Option<String>| price.as_ref().map(|price| match currency {
+            Some(currency) => format!("{price} {currency}"),
+            None => price.to_owned(),
+        });
+        let parts = match self {
+            StructuredData::Article(a) => vec![a.author.clone(), a.date_published.clone()],
+            StructuredData::Product(p) => vec![p.brand.clone(), price(&p.price, &p.price_currency), rating],
+            StructuredData::Recipe(r) => vec![r.total_time.clone(), r.recipe_yield.clone(), rating],
+            StructuredData::Person(p) => vec![p.job_title.clone()],
+            StructuredData::Organization(_) => Vec::new(),
+            StructuredData::Event(e) => vec![e.start_date.clone(), e.location.clone()],
+        };
+        std::iter::once(self.schema_type().to_owned()).chain(parts.into_iter().flatten()).collect::<Vec<_>>().join(" · ")
+    }
+}
+
 impl DocumentResult {
     pub fn sort_paths(&mut self) {
         // TODO: sort using more advanced algorithm
@@ -117,7 +194,7 @@ impl DocumentResult {
             (_, Some(h1)) => h1,
             (None, None) => return false,
         };
-        let title_words = title.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3).map(|w| w.to_lowercase()).collect::<Vec<_>>();
+        let title_words = tokenize(title);
 
         fn words_match_query(comp: &QueryComp, words: &[String]) -> bool {
             match comp {
@@ -155,6 +232,17 @@ impl DocumentResult {
         }
     }
 
+    /// Resolves the preview image against the path of the document
+    pub fn format_preview_image(&self, conn_status: &ConnectionStatus) -> Option<String> {
+        let src = self.preview_image.as_ref()?;
+        resolve_media_src(src, self.paths.first()).map(|addr| format_media_addr(addr, conn_status))
+    }
+
+    /// Summarizes the first structured data item of the result, if any
+    pub fn format_structured_data(&self) -> Option<String> {
+        self.structured_data.first().map(|item| item.format_summary())
+    }
+
     pub fn format_best_addr(&self) -> String {
         let mut best_addr = match self.paths.first() {
             Some(f) => f.as_slice(),
@@ -187,7 +275,7 @@ impl DocumentResult {
         // TODO: this is a copy of daemon code
         fn extract_score(extract: &str, query: &[&String]) -> usize {
             let mut score = 0;
-            let mut extract_words = extract.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3).map(|w| w.to_lowercase()).collect::<Vec<_>>();
+            let mut extract_words = tokenize(extract);
             if extract_words.is_empty() {
                 return 0;
             }
@@ -216,30 +304,61 @@ impl DocumentResult {
             (None, Some(extract)) => extract,
             (None, None) => return VList::new(),
         };
-        let mut i = 0;
+        // Overlapping tokens (such as CJK bigrams) are merged into a single bolded range
+        let mut bolded: Vec<(usize, usize)> = Vec::new();
+        for token in tokens(desc) {
+            if !query_terms.contains(&&token.word) {
+                continue;
+            }
+            match bolded.last_mut() {
+                Some((_, end)) if token.start <= *end => *end = (*end).max(token.end),
+                _ => bolded.push((token.start, token.end)),
+            }
+        }
+
         let mut added = 0;
         let mut vlist = VList::new();
-        for part in desc.split_inclusive(|c: char| !c.is_ascii_alphanumeric()) {
-            let part_len = part.len();
-            let word = part.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
-            if word.len() >= 3 && query_terms.contains(&&word.to_lowercase()) {
-                if i - added > 0 {
-                    let unbolded_text = desc[added..i].to_string();
-                    vlist.add_child(VText::new(unbolded_text).into());
-                }
-                let bolded_text = desc[i..i + word.len()].to_string();
-                let mut b_el = VTag::new("b");
-                b_el.add_child(VText::new(bolded_text).into());
-                vlist.add_child(b_el.into());
-                added = i + word.len();
+        for (start, end) in bolded {
+            if start > added {
+                let unbolded_text = desc[added..start].to_string();
+                vlist.add_child(VText::new(unbolded_text).into());
             }
-            i += part_len;
+            let bolded_text = desc[start..end].to_string();
+            let mut b_el = VTag::new("b");
+            b_el.add_child(VText::new(bolded_text).into());
+            vlist.add_child(b_el.into());
+            added = end;
         }
-        if i - added > 0 {
-            let unbolded_text = desc[added..i].to_string();
+        if desc.len() > added {
+            let unbolded_text = desc[added..].to_string();
             vlist.add_child(VText::new(unbolded_text).into());
         }
 
         vlist
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_media_src() {
+        let doc_path = vec![String::from("example.com"), String::from("blog"), String::from("post.html")];
+        let resolve = |src: &str| resolve_media_src(src, Some(&doc_path));
+
+        assert_eq!(resolve("image.png").as_deref(), Some("ipns://example.com/blog/image.png"));
+        assert_eq!(resolve("./image.png?v=2#top").as_deref(), Some("ipns://example.com/blog/image.png"));
+        assert_eq!(resolve("../image.png").as_deref(), Some("ipns://example.com/image.png"));
+        assert_eq!(resolve("../../image.png"), None);
+        assert_eq!(resolve("/assets/image.png").as_deref(), Some("ipns://example.com/assets/image.png"));
+        assert_eq!(resolve("//cdn.example.org/image.png").as_deref(), Some("https://cdn.example.org/image.png"));
+        assert_eq!(resolve("https://example.org/image.png").as_deref(), Some("https://example.org/image.png"));
+        assert_eq!(resolve("data:image/png;base64,AAAA"), None);
+        assert_eq!(resolve("javascript:alert(1)"), None);
+
+        let cid_path = vec![String::from("bafybeigdyrzt"), String::from("index.html")];
+        assert_eq!(resolve_media_src("image.png", Some(&cid_path)).as_deref(), Some("ipfs://bafybeigdyrzt/image.png"));
+        assert_eq!(resolve_media_src("image.png", None), None);
+    }
+}
@@ -4,9 +4,10 @@
 mod result;
 mod scores;
 mod word_count;
+mod structured_data;
 mod ranked;
 mod format;
 mod verification;
 mod grouped;
 
-pub use {result::*, scores::*, word_count::*, ranked::*, format::*, verification::*, grouped::*};
+pub use {result::*, scores::*, word_count::*, structured_data::*, ranked::*, format::*, verification::*, grouped::*};
@@ -63,7 +63,7 @@ impl RankedResults {
 
         self.ipns_scores.insert(res.cid.clone(), res.ipns_score());
 
-        self.lang_scores.insert(res.cid.clone(), res.lang_score(Lang::English));
+        self.lang_scores.insert(res.cid.clone(), res.lang_score(Lang::from_query(query)));
 
         if res.is_grouping_result(query) {
             // FIXME: handle the case where a grouping result is itself grouped under another grouping result
@@ -186,7 +186,8 @@ impl RankedResults {
             let cid = cid.clone();
             let untrusted_result = untrusted_result.clone();
             spawn_local(async move {
-                let trusted_result = match get_result(rpc_addr, search_id, &cid).await {
+                let path = untrusted_result.paths.first().map(|path| path.as_slice());
+                let trusted_result = match get_result(rpc_addr, search_id, &cid, path).await {
                     Ok(Some(result)) => result,
                     Ok(None) => {
                         link.send_message(ResultsMessage::MaliciousResult(cid));
@@ -2,19 +2,20 @@ use crate::prelude::*;
 
 impl Query {
     fn map_count(&self, counts: &HashMap<&String, f64>) -> f64 {
-        self.root.clone_only_words().map(|r| r.map_counts(counts)).unwrap_or(1.0)
+        let lang = self.lang_filter().map(|lang| lang.as_str());
+        self.root.clone_only_words().map(|r| r.map_counts(counts, lang)).unwrap_or(1.0)
     }
 }
 
 impl QueryComp {
     #[track_caller]
-    fn map_counts(&self, counts: &HashMap<&String, f64>) -> f64 {
+    fn map_counts(&self, counts: &HashMap<&String, f64>, lang: Option<&str>) -> f64 {
         match self {
-            QueryComp::Word(w) => counts.get(w).copied().unwrap_or(0.0),
+            QueryComp::Word(w) => counts.get(w).copied().unwrap_or(0.0) * word_lists::word_rarity(w, lang),
             QueryComp::Filter { .. } => panic!("QueryComp::map_counts() called on filter"),
             QueryComp::Not(_) => panic!("QueryComp::map_counts() called on not"),
             QueryComp::NAmong { n, among } => {
-                let mut mapped_counts = among.iter().map(|c| c.map_counts(counts)).collect::<Vec<_>>();
+                let mut mapped_counts = among.iter().map(|c| c.map_counts(counts, lang)).collect::<Vec<_>>();
                 mapped_counts.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
                 mapped_counts.into_iter().take(*n).sum::<f64>() / *n as f64
             }
@@ -33,15 +34,7 @@ impl DocumentResult {
         let term_count = query.map_count(&counts);
         
         // Title is counted separately as it is not part of the document body
-        let title_words  = self.title
-            .as_ref()
-            .map(|t| t
-                .to_lowercase()
-                .split(|c: char| !c.is_ascii_alphanumeric())
-                .filter(|w| w.len() >= 3)
-                .map(|w| w.to_string())
-            .collect::<Vec<_>>())
-            .unwrap_or_default();
+        let title_words = self.title.as_deref().map(tokenize).unwrap_or_default();
         let title_word_count = title_words.len();
         let title_word_count = title_word_count as f64 * 12.0;
         let mut counts = HashMap::new();
@@ -64,15 +57,7 @@ impl DocumentResult {
                 let title_words_some = match title_words.take() {
                     Some(title_words) => title_words,
                     None => {
-                        let Some(title_words_some) = self.title
-                            .as_ref()
-                            .map(|t| t
-                                .to_lowercase()
-                                .split(|c: char| !c.is_ascii_alphanumeric())
-                                .filter(|w| w.len() >= 3)
-                                .map(|w| w.to_string())
-                            .collect::<Vec<_>>())
-                            else {continue};
+                        let Some(title_words_some) = self.title.as_deref().map(tokenize) else {continue};
                         title_words_some
                     }
                 };
@@ -117,18 +102,16 @@ impl DocumentResult {
             Some(common_words) => common_words,
             None => {
                 let mut words = Vec::new();
-                let description_lowercase = self.description.as_ref().map(|d| d.to_lowercase());
-                let extract_lowercase = self.extract.as_ref().map(|e| e.to_lowercase());
-                if let Some(description) = &description_lowercase {
-                    words.extend(description.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3));
+                if let Some(description) = &self.description {
+                    words.extend(tokenize(description));
                 }
-                if let Some(extract) = &extract_lowercase {
-                    words.extend(extract.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3));
+                if let Some(extract) = &self.extract {
+                    words.extend(tokenize(extract));
                 }
 
                 let words_bytes = words.iter().map(|w| w.len()).sum::<usize>();
                 let lang_words = requested_lang.common_words();
-                let common_words_bytes = words.iter().filter(|w| lang_words.sorted_contains(w)).map(|w| w.len()).sum::<usize>();
+                let common_words_bytes = words.iter().filter(|w| lang_words.sorted_contains(w.as_str())).map(|w| w.len()).sum::<usize>();
                 common_words_bytes as f64 / words_bytes as f64
             },
         };
@@ -0,0 +1 @@
+../../../daemon/src/result/structured_data.rs
\ No newline at end of file
@@ -9,6 +9,11 @@ impl DocumentResult {
             && self.h1 == trusted.h1
             && self.description == trusted.description
             && (self.extract.is_none() || self.extract == trusted.extract || trusted.extract.is_none())
+            && self.preview_image == trusted.preview_image
+            && self.media == trusted.media
+            && self.site_name == trusted.site_name
+            && self.published == trusted.published
+            && self.structured_data == trusted.structured_data
             && self.term_counts == trusted.term_counts
             && self.word_count == trusted.word_count
             && (self.common_words.is_none() || self.common_words == trusted.common_words || trusted.common_words.is_none())
@@ -0,0 +1 @@
+../../daemon/src/tokenizer.rs
\ No newline at end of file
@@ -1,31 +1,117 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+use unicode_normalization::UnicodeNormalization;
+
+/// Words shorter than this are never indexed (see `MIN_WORD_LENGTH` in the tokenizer)
+const MIN_WORD_LENGTH: usize = 3;
+/// Only the most frequent words of each language are ranked, to keep the binary small
+const MAX_WORD_RANK: u32 = 50_000;
+
+/// Same as `normalize_word` in the tokenizer, so that listed words match indexed words
+fn normalize_word(word: &str) -> String {
+    word.nfkd().filter(|c| !('\u{0300}'..='\u{036F}').contains(c)).nfc().flat_map(char::to_lowercase).collect()
+}
+
+fn keep_word(word: &str) -> bool {
+    word.chars().count() >= MIN_WORD_LENGTH && word.chars().all(char::is_alphanumeric)
+}
+
+/// Lists the `.txt` files of a data directory, sorted by name
+fn list_txt_files(dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut paths = entries
+        .map(|entry| entry.expect("Could not read data directory entry").path())
+        .filter(|path| path.extension().map(|ext| ext == "txt").unwrap_or(false))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+/// Reads a frequency list, with one word per line from the most frequent to the least, optionally followed by its count.
+/// Returns (word, rank) pairs sorted by word. Words that normalize to the same one keep the best rank.
+fn read_ranks(content: &str) -> Vec<(String, u32)> {
+    let mut ranks = BTreeMap::new();
+    for (i, line) in content.lines().filter(|line| !line.trim().is_empty()).take(MAX_WORD_RANK as usize).enumerate() {
+        let word = normalize_word(line.split_whitespace().next().unwrap_or_default());
+        if keep_word(&word) {
+            ranks.entry(word).or_insert(i as u32 + 1);
+        }
+    }
+    ranks.into_iter().collect()
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
-    
-    // Download words
-    let url = "https://norvig.com/ngrams/count_1w.txt";
-    let rep = reqwest::blocking::get(url).expect("Could not download wordlist");
-
-    // Keep most common 5000 words
-    let mut words = Vec::new();
-    for line in rep.text().expect("Invalid data").lines() {
-        let Some(word) = line.split_once('\t').map(|(w, _)| w) else {continue};
-        words.push(word.to_string());
-        if words.len() >= 5000 {
-            break
+    println!("cargo:rerun-if-changed=data");
+    println!("cargo:rerun-if-changed=data/frequencies");
+
+    // List languages
+    let paths = list_txt_files("data");
+    let mut code = String::new();
+    let mut langs = Vec::new();
+    for path in paths {
+        let lang = path.file_stem().and_then(|stem| stem.to_str()).expect("Invalid word list name").to_owned();
+        let content = fs::read_to_string(&path).expect("Could not read word list");
+        let mut words = content
+            .lines()
+            .map(normalize_word)
+            .filter(|word| keep_word(word))
+            .collect::<Vec<_>>();
+        words.sort();
+        words.dedup();
+
+        code.push_str(&format!("pub const WORDS_{}: &[&str] = &[", lang.to_uppercase()));
+        for word in words.iter() {
+            code.push_str(&format!("{word:?}, "));
         }
+        code.push_str("];\n");
+        langs.push(lang);
     }
-    words.sort();
 
-    // Create rfust code
-    let mut code = String::new();
-    code.push_str("pub const WORDS_EN: &[&str] = &[");
-    for word in words.iter() {
-        code.push('"');
-        code.push_str(word);
-        code.push_str("\", ");
+    code.push_str("\n/// Languages for which a word list is available, as ISO 639-1 codes\n");
+    code.push_str("pub const LANGUAGES: &[&str] = &[");
+    for lang in langs.iter() {
+        code.push_str(&format!("{lang:?}, "));
+    }
+    code.push_str("];\n");
+
+    code.push_str("\n/// Returns the sorted list of common words of a language, given its ISO 639-1 code\n");
+    code.push_str("pub fn common_words(lang: &str) -> Option<&'static [&'static str]> {\n    match lang {\n");
+    for lang in langs.iter() {
+        code.push_str(&format!("        {lang:?} => Some(WORDS_{}),\n", lang.to_uppercase()));
+    }
+    code.push_str("        _ => None,\n    }\n}\n");
+
+    // Frequency lists
+    let mut ranked_langs = Vec::new();
+    for path in list_txt_files("data/frequencies") {
+        let lang = path.file_stem().and_then(|stem| stem.to_str()).expect("Invalid frequency list name").to_owned();
+        let content = fs::read_to_string(&path).expect("Could not read frequency list");
+        code.push_str(&format!("pub const RANKS_{}: &[(&str, u32)] = &[", lang.to_uppercase()));
+        for (word, rank) in read_ranks(&content) {
+            code.push_str(&format!("({word:?}, {rank}), "));
+        }
+        code.push_str("];\n");
+        ranked_langs.push(lang);
+    }
+
+    code.push_str("\n/// Rank of the least frequent words of frequency lists\n");
+    code.push_str(&format!("pub const MAX_WORD_RANK: u32 = {MAX_WORD_RANK};\n"));
+
+    code.push_str("\n/// Languages for which a frequency list is available, as ISO 639-1 codes\n");
+    code.push_str("pub const RANKED_LANGUAGES: &[&str] = &[");
+    for lang in ranked_langs.iter() {
+        code.push_str(&format!("{lang:?}, "));
+    }
+    code.push_str("];\n");
+
+    code.push_str("\n/// Returns the (word, rank) pairs of the most frequent words of a language sorted by word, given its ISO 639-1 code\n");
+    code.push_str("pub fn word_ranks(lang: &str) -> Option<&'static [(&'static str, u32)]> {\n    match lang {\n");
+    for lang in ranked_langs.iter() {
+        code.push_str(&format!("        {lang:?} => Some(RANKS_{}),\n", lang.to_uppercase()));
     }
-    code.push_str("];");
+    code.push_str("        _ => None,\n    }\n}\n");
 
     // Write code to file
-    std::fs::write("src/word_lists.rs", code).expect("Could not write word_lists.rs")
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(Path::new(&out_dir).join("word_lists.rs"), code).expect("Could not write word_lists.rs")
 }
@@ -1,6 +1,65 @@
-mod word_lists;
+mod word_lists {
+    include!(concat!(env!("OUT_DIR"), "/word_lists.rs"));
+}
 pub use word_lists::*;
 
+/// Rarity of words that are in the word list of their language, for languages without a frequency list
+pub const COMMON_WORD_RARITY: f64 = 0.1;
+
+/// Returns the rank of a word among the most frequent words of a language, 1 being the most frequent.
+/// Returns `None` if the word isn't ranked or if the language has no frequency list.
+pub fn word_rank(lang: &str, word: &str) -> Option<u32> {
+    let ranks = word_ranks(lang)?;
+    ranks.binary_search_by(|(ranked, _)| (*ranked).cmp(word)).ok().map(|i| ranks[i].1)
+}
+
+/// Estimated share of the words of a text that are the word of this rank, following Zipf's law
+fn zipf_frequency(rank: u32) -> f64 {
+    // The harmonic number H(MAX_WORD_RANK), approximated with the Euler–Mascheroni constant
+    let harmonic = (MAX_WORD_RANK as f64).ln() + 0.5772;
+    1.0 / (rank as f64 * harmonic)
+}
+
+/// Estimates how often a word is used in a language, as a share of all the words of a text.
+/// Returns `None` if the word isn't ranked or if the language has no frequency list.
+pub fn word_frequency(lang: &str, word: &str) -> Option<f64> {
+    word_rank(lang, word).map(zipf_frequency)
+}
+
+/// Inverse document frequency of the word of this rank, normalized to 1.0 for the least frequent ranked words
+fn rank_rarity(rank: u32) -> f64 {
+    let idf = -zipf_frequency(rank).ln();
+    let max_idf = -zipf_frequency(MAX_WORD_RANK).ln();
+    (idf / max_idf).clamp(0.0, 1.0)
+}
+
+/// Estimates how rare a word is, from about 0.2 for the most frequent words to 1.0 for words that aren't ranked.
+/// Rare words tell more about a document, so they should weigh more when ranking.
+/// The rarity is an inverse document frequency derived from [word_frequency].
+/// Languages without a frequency list fall back to their word list, where listed words get [COMMON_WORD_RARITY].
+/// When the language is unknown, the word is looked up in all languages and its most frequent use is kept.
+pub fn word_rarity(word: &str, lang: Option<&str>) -> f64 {
+    if let Some(lang) = lang {
+        if word_ranks(lang).is_some() {
+            return word_rank(lang, word).map(rank_rarity).unwrap_or(1.0);
+        }
+        if let Some(words) = common_words(lang) {
+            return match words.sorted_contains(word) {
+                true => COMMON_WORD_RARITY,
+                false => 1.0,
+            };
+        }
+    }
+
+    if let Some(rank) = RANKED_LANGUAGES.iter().filter_map(|lang| word_rank(lang, word)).min() {
+        return rank_rarity(rank);
+    }
+    match LANGUAGES.iter().filter_map(|lang| common_words(lang)).any(|words| words.sorted_contains(word)) {
+        true => COMMON_WORD_RARITY,
+        false => 1.0,
+    }
+}
+
 pub trait HackTraitSortedContains<T> {
     /// Like `contains()` but optimized for sorted arrays.
     fn sorted_contains(&self, item: T) -> bool;
@@ -17,3 +76,27 @@ impl<T: Ord> HackTraitSortedContains<T> for [T] {
         self.binary_search(&item).is_ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_rarity() {
+        assert!(rank_rarity(1) > 0.1 && rank_rarity(1) < 0.3);
+        assert!(rank_rarity(1) < rank_rarity(100));
+        assert!(rank_rarity(100) < rank_rarity(10_000));
+        assert!((rank_rarity(MAX_WORD_RANK) - 1.0).abs() < 1e-9);
+        assert!((zipf_frequency(1) / zipf_frequency(10) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_word_rarity_fallback() {
+        if word_ranks("en").is_none() {
+            assert_eq!(word_rarity("the", Some("en")), COMMON_WORD_RARITY);
+        }
+        assert_eq!(word_rarity("admarus", Some("en")), 1.0);
+        assert_eq!(word_rarity("admarus", None), 1.0);
+        assert_eq!(word_rarity("admarus", Some("xx")), 1.0);
+    }
+}